@@ -1,14 +1,52 @@
-use crate::config::AppConfig;
+use crate::config::{self, AppConfig, DetailMode, FilterState, Session};
+use crate::dashboards;
+use crate::export::{self, ExportFormat};
 use crate::filter_field::FilterField;
-use crate::opensearch::{self, LogEntry};
+use crate::opensearch::{self, LogEntry, LogQuery, LogResult, SortOrder, TimeRange};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 const ALL: &str = "ALL";
+const DEFAULT_PROFILE: &str = "production";
+const DEFAULT_TIME_RANGE: &str = "5m";
+const TIME_RANGES: &[&str] = &["1m", "5m", "15m", "30m", "1h", "3h", "6h", "12h", "24h", "3d", "7d"];
+/// Time ranges around a pinned anchor time.
+const ANCHOR_RANGES: &[&str] = &["±1m", "±5m", "±15m", "±1h", "±6h"];
+const DEFAULT_ANCHOR_RANGE: &str = "±15m";
+const DEFAULT_LIMIT: &str = "50";
+
+/// OpenSearch's default `index.max_result_window`; `from + size` may not exceed it.
+const MAX_RESULT_WINDOW: u64 = 10_000;
+
+/// How long rows that arrived on a refresh stay highlighted.
+pub const FLASH_DURATION: Duration = Duration::from_millis(1500);
+
+/// How long typing has to pause before live search re-runs the query.
+const LIVE_SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How many past searches are kept for recall with Up/Down.
+const SEARCH_HISTORY_LEN: usize = 100;
+
+/// Roughly how many bars the log volume histogram has.
+const HISTOGRAM_BUCKETS: i64 = 60;
+/// Bucket sizes the histogram picks from, shortest first.
+const HISTOGRAM_INTERVALS: &[&str] = &[
+    "1s", "5s", "10s", "30s", "1m", "5m", "10m", "30m", "1h", "3h", "12h", "1d", "7d",
+];
+
+/// A span of time, start first.
+pub type TimeWindow = (DateTime<Utc>, DateTime<Utc>);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Pane {
     Profile,
     Application,
     Severity,
+    Thread,
     TimeRange,
     Limit,
     Search,
@@ -16,9 +54,86 @@ pub enum Pane {
     SearchFields,
     Logs,
     LogContext,
+    Detail,
+    ConfirmDefaults,
+    GotoPage,
+    Find,
+    SaveEntry,
+    CountDistinct,
+    PivotField,
+    QueryDebug,
+    Help,
+}
+
+pub const CONTEXT_MENU_OPTIONS: &[&str] = &[
+    "Copy to clipboard",
+    "Open in editor",
+    "Save as text…",
+    "Save as JSON…",
+    "Copy trace ID",
+    "Open trace",
+];
+
+/// Result of a background log fetch, tagged with the request it answers.
+pub struct FetchOutcome {
+    seq: u64,
+    page: u64,
+    label: String,
+    query: LogQuery,
+    result: anyhow::Result<LogResult>,
+}
+
+/// Result of a background histogram fetch, tagged with the filters it is for.
+pub struct HistogramOutcome {
+    query: LogQuery,
+    interval: &'static str,
+    result: anyhow::Result<Vec<(i64, u64)>>,
+}
+
+/// Result of a background distinct value count for `field`.
+pub struct DistinctOutcome {
+    field: String,
+    result: anyhow::Result<u64>,
+}
+
+/// A character range of the selected entry's message being picked in the
+/// detail view. Both ends are char indices and both are included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSelection {
+    pub anchor: usize,
+    pub cursor: usize,
 }
 
-pub const CONTEXT_MENU_OPTIONS: &[&str] = &["Copy to clipboard", "Open in editor"];
+impl TextSelection {
+    pub fn range(&self) -> std::ops::RangeInclusive<usize> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+}
+
+/// Entries of one application on the current page, for the grouped view.
+pub struct AppGroup {
+    pub application: String,
+    /// Indices into `App::logs`, in page order.
+    pub entries: Vec<usize>,
+    /// The most severe entry, the first on the page among equals.
+    pub worst: usize,
+    /// The entry with the newest timestamp.
+    pub latest: usize,
+}
+
+/// A row of the grouped view.
+pub enum GroupRow {
+    Group(AppGroup),
+    /// An entry of an expanded group, as an index into `App::logs`.
+    Entry(usize),
+}
+
+/// A previously fetched page, kept for instant paging back.
+struct CachedPage {
+    query: LogQuery,
+    fetched_at: Instant,
+    result: LogResult,
+}
 
 pub struct App {
     pub config: AppConfig,
@@ -27,32 +142,186 @@ pub struct App {
     pub profile_filter: FilterField,
     pub app_filter: FilterField,
     pub severity_filter: FilterField,
+    /// Leave the selected severities out instead of showing only them.
+    pub severity_exclude: bool,
+    pub thread_filter: FilterField,
     pub time_filter: FilterField,
+    /// The time range was picked by hand, so severity changes keep it.
+    pub time_overridden: bool,
+    /// Time the time range presets are centered on, instead of now.
+    pub anchor: Option<DateTime<Utc>>,
+    /// Exact time span typed or set from two marked entries, overriding the presets.
+    pub custom_range: Option<TimeWindow>,
     pub limit_filter: FilterField,
     pub search_text: String,
+    /// Past searches, oldest first.
+    pub search_history: Vec<String>,
+    /// Entry of `search_history` shown in the search box while recalling.
+    search_history_index: Option<usize>,
+    /// What was typed before recalling older searches.
+    search_draft: String,
+    /// Search whose matches are left out of the results.
+    pub exclude_text: String,
+    /// Search body edited by hand, sent instead of the filters while set.
+    pub raw_query: Option<Value>,
+    /// Scroll offset of the generated query popup.
+    pub query_scroll: u16,
+    /// Term for finding rows within the loaded page, without refetching.
+    pub find_text: String,
+    /// Hide the rows not matching the find term instead of jumping between them.
+    pub find_filter: bool,
+    /// Stepping between find matches: `n`/`N` move the selection until `Esc`.
+    pub finding: bool,
     pub search_mode_filter: FilterField,
     pub search_fields_filter: FilterField,
+    pub sort: SortOrder,
 
     pub logs: Vec<LogEntry>,
+    /// Entries per severity on the current page, most severe first.
+    pub severity_counts: Vec<(String, usize)>,
     pub log_index: usize,
+    /// Rows marked with Space, oldest mark first; at most two are kept.
+    pub marked: Vec<usize>,
+    /// Show timestamps as their age instead of the time of day.
+    pub relative_time: bool,
+    /// Fit more of the table: no gap under the header, no side borders and a
+    /// narrower logger column.
+    pub dense: bool,
+    /// Wrap messages onto a second line instead of cutting them off.
+    pub two_line: bool,
+    /// Show the trace ID column.
+    pub show_trace_id: bool,
+    /// Show the page grouped by application instead of as a flat list.
+    pub grouped: bool,
+    /// Selected row of the grouped view.
+    pub group_cursor: usize,
+    /// Applications whose entries are listed in the grouped view.
+    expanded_groups: HashSet<String>,
+    /// Show stacktraces inline under their rows.
+    pub stacktraces_expanded: bool,
+    /// Rows whose stacktrace is shown opposite to `stacktraces_expanded`.
+    toggled_stacktraces: HashSet<usize>,
     pub total_hits: u64,
     pub page: u64,
     pub context_cursor: usize,
+    /// Page number being typed into the goto prompt.
+    pub goto_input: String,
+    /// Format and destination of the entry being saved to a file.
+    pub save_format: ExportFormat,
+    pub save_path: String,
+    /// Save the whole page rather than the selected entry.
+    pub save_page: bool,
+    /// Field typed into the distinct count prompt.
+    pub distinct_field: String,
+    pub detail_scroll: u16,
+    pub detail_mode: DetailMode,
+    /// Show the selected message as a hex dump instead of `detail_mode`.
+    pub detail_hex: bool,
+    /// Soft-wrap long lines in the detail view.
+    pub detail_wrap: bool,
+    /// Part of the message being selected for copying, if selecting.
+    pub detail_selection: Option<TextSelection>,
+    /// Selected row of the field list to search by from the detail view.
+    pub pivot_cursor: usize,
+    /// Field path and value entries must have, from the last pivot.
+    pub pivot: Option<(String, String)>,
+
+    /// Whether a fetch is in flight.
+    pub pending: bool,
+    /// Sequence number of the most recently started fetch.
+    fetch_seq: u64,
+    /// Client for the configured cluster, shared by all requests.
+    client: opensearch::SharedClient,
+    fetch_tx: mpsc::UnboundedSender<FetchOutcome>,
+    /// The fetch in flight, aborted when a newer one supersedes it.
+    fetch_task: Option<JoinHandle<()>>,
+    fetch_rx: mpsc::UnboundedReceiver<FetchOutcome>,
+    /// Entry counts per `histogram_interval` across the time range, oldest first.
+    pub histogram: Vec<(i64, u64)>,
+    pub histogram_interval: &'static str,
+    /// Filters the histogram was last requested for.
+    histogram_query: Option<LogQuery>,
+    histogram_tx: mpsc::UnboundedSender<HistogramOutcome>,
+    histogram_rx: mpsc::UnboundedReceiver<HistogramOutcome>,
+    histogram_task: Option<JoinHandle<()>>,
+    distinct_tx: mpsc::UnboundedSender<DistinctOutcome>,
+    distinct_rx: mpsc::UnboundedReceiver<DistinctOutcome>,
+    /// The distinct count in flight, aborted when another one is started.
+    distinct_task: Option<JoinHandle<()>>,
+    /// When the search text was last edited, while a live search is due.
+    search_edited_at: Option<Instant>,
+    /// Recently fetched pages, most recent last.
+    page_cache: VecDeque<CachedPage>,
+    /// Periodically refetch the first page while it is shown.
+    pub live_tail: bool,
+    /// When live tail last refetched.
+    tailed_at: Instant,
+    /// Live tail stopped refetching because nobody was pressing keys.
+    pub tail_paused: bool,
+    /// When a key was last pressed.
+    last_input: Instant,
+    /// Entries that arrived above the selection while it was held in place.
+    pub new_entries: usize,
+    /// Entries at the top of the page that live tail brought in since a key
+    /// was last pressed, marked off from the ones seen before.
+    pub unseen_entries: usize,
+    /// A key was pressed since live tail last brought in entries.
+    looked: bool,
+    /// Select the newest entry after every refresh, even when scrolled down.
+    pub follow: bool,
+    /// Entries at the top that the last refresh brought in while following,
+    /// highlighted until the selection moves.
+    pub followed_new: usize,
+    /// Hit count before a rerun, to report the difference once it completes.
+    rerun_total: Option<u64>,
+    /// The query the shown logs answer.
+    shown_query: Option<LogQuery>,
+    /// Rows that weren't in the previous result for the same query.
+    pub flashed_rows: HashSet<usize>,
+    pub flashed_at: Instant,
 
     pub status: String,
+    /// Warning appended to the status once the next fetch completes.
+    pub notice: Option<String>,
+    /// The last request failed because the AWS credentials expired.
+    pub credentials_expired: bool,
+    /// Row to select once a restored session's page has loaded.
+    restore_index: Option<usize>,
 }
 
 impl App {
     pub fn new(config: AppConfig) -> Self {
+        let (fetch_tx, fetch_rx) = mpsc::unbounded_channel();
+        let (histogram_tx, histogram_rx) = mpsc::unbounded_channel();
+        let (distinct_tx, distinct_rx) = mpsc::unbounded_channel();
         Self {
+            detail_mode: config.detail_mode,
+            relative_time: config.relative_timestamps,
+            dense: config.dense_rows,
+            two_line: config.two_line_rows,
+            show_trace_id: config.show_trace_id,
             config,
             focused: Pane::Logs,
             profile_filter: FilterField::new(),
             app_filter: FilterField::new(),
             severity_filter: FilterField::new(),
+            severity_exclude: false,
+            thread_filter: FilterField::new(),
             time_filter: FilterField::new(),
+            time_overridden: false,
+            anchor: None,
+            custom_range: None,
             limit_filter: FilterField::new(),
             search_text: String::new(),
+            search_history: Vec::new(),
+            search_history_index: None,
+            search_draft: String::new(),
+            exclude_text: String::new(),
+            raw_query: None,
+            query_scroll: 0,
+            find_text: String::new(),
+            find_filter: false,
+            finding: false,
             search_mode_filter: {
                 let mut f = FilterField::new();
                 f.set_items(vec!["Each word".to_string(), "Exact".to_string()]);
@@ -60,15 +329,81 @@ impl App {
             },
             search_fields_filter: {
                 let mut f = FilterField::new();
-                f.set_items(vec!["All fields".to_string(), "Message only".to_string()]);
+                f.set_items(
+                    ["All fields", "Message only", "Logger only", "Thread only", "Application only"]
+                        .map(String::from)
+                        .to_vec(),
+                );
                 f
             },
+            sort: SortOrder::default(),
             logs: Vec::new(),
+            severity_counts: Vec::new(),
             log_index: 0,
+            marked: Vec::new(),
+            grouped: false,
+            group_cursor: 0,
+            expanded_groups: HashSet::new(),
+            stacktraces_expanded: false,
+            toggled_stacktraces: HashSet::new(),
             total_hits: 0,
             page: 1,
             context_cursor: 0,
+            goto_input: String::new(),
+            save_format: ExportFormat::default(),
+            save_path: String::new(),
+            save_page: false,
+            distinct_field: "logger".to_string(),
+            detail_scroll: 0,
+            detail_hex: false,
+            detail_wrap: false,
+            detail_selection: None,
+            pivot_cursor: 0,
+            pivot: None,
+            pending: false,
+            fetch_seq: 0,
+            client: opensearch::SharedClient::default(),
+            fetch_tx,
+            fetch_task: None,
+            fetch_rx,
+            histogram: Vec::new(),
+            histogram_interval: HISTOGRAM_INTERVALS[0],
+            histogram_query: None,
+            histogram_tx,
+            histogram_rx,
+            distinct_tx,
+            distinct_rx,
+            distinct_task: None,
+            histogram_task: None,
+            search_edited_at: None,
+            page_cache: VecDeque::new(),
+            live_tail: false,
+            tailed_at: Instant::now(),
+            tail_paused: false,
+            last_input: Instant::now(),
+            new_entries: 0,
+            unseen_entries: 0,
+            looked: false,
+            follow: false,
+            followed_new: 0,
+            rerun_total: None,
+            shown_query: None,
+            flashed_rows: HashSet::new(),
+            flashed_at: Instant::now(),
             status: "Loading filters...".to_string(),
+            notice: None,
+            credentials_expired: false,
+            restore_index: None,
+        }
+    }
+
+    /// Connection settings for the configured cluster.
+    pub fn cluster(&self) -> opensearch::Cluster {
+        opensearch::Cluster {
+            endpoint_url: self.config.endpoint_url.clone(),
+            auth: self.config.auth.clone(),
+            ca_cert_path: self.config.ca_cert_path.clone(),
+            indices: self.config.indices.clone(),
         }
     }
 
@@ -76,34 +411,182 @@ impl App {
         self.profile_filter.selected_value()
     }
 
-    pub fn selected_app(&self) -> Option<&str> {
-        self.app_filter.selected_value().filter(|v| *v != ALL)
+    /// The applications to show logs of; empty for all of them.
+    pub fn selected_apps(&self) -> Vec<&str> {
+        let checked = self.app_filter.checked_values();
+        if !checked.is_empty() {
+            return checked;
+        }
+        self.app_filter.selected_value().filter(|v| *v != ALL).into_iter().collect()
     }
 
-    pub fn selected_severity(&self) -> Option<&str> {
-        self.severity_filter
-            .selected_value()
-            .filter(|v| *v != ALL)
+    /// The severities picked, to show or with `severity_exclude` to leave
+    /// out; empty for all of them.
+    pub fn selected_severities(&self) -> Vec<&str> {
+        let checked = self.severity_filter.checked_values();
+        if !checked.is_empty() {
+            return checked;
+        }
+        self.severity_filter.selected_value().filter(|v| *v != ALL).into_iter().collect()
     }
 
-    pub fn selected_time_range(&self) -> &str {
-        self.time_filter
-            .selected_value()
-            .map(|v| match v {
-                "1m" => "now-1m",
-                "5m" => "now-5m",
-                "15m" => "now-15m",
-                "30m" => "now-30m",
-                "1h" => "now-1h",
-                "3h" => "now-3h",
-                "6h" => "now-6h",
-                "12h" => "now-12h",
-                "24h" => "now-24h",
-                "3d" => "now-3d",
-                "7d" => "now-7d",
-                _ => "now-5m",
-            })
-            .unwrap_or("now-5m")
+    pub fn selected_thread(&self) -> Option<&str> {
+        self.thread_filter.selected_value().filter(|v| *v != ALL)
+    }
+
+    pub fn selected_time_range(&self) -> TimeRange {
+        if let Some((from, to)) = self.custom_range {
+            return TimeRange::Between {
+                gte: from.to_rfc3339_opts(SecondsFormat::Millis, true),
+                lte: to.to_rfc3339_opts(SecondsFormat::Millis, true),
+            };
+        }
+        let selected = self.time_filter.selected_value();
+        if let Some(anchor) = self.anchor {
+            let span = selected
+                .and_then(|v| v.strip_prefix('±'))
+                .and_then(duration_parts)
+                .or_else(|| duration_parts(DEFAULT_ANCHOR_RANGE.trim_start_matches('±')))
+                .unwrap_or_default();
+            let at = anchor.to_rfc3339_opts(SecondsFormat::Millis, true);
+            return TimeRange::Between {
+                gte: format!("{}||{}", at, date_math(&span, '-')),
+                lte: format!("{}||{}", at, date_math(&span, '+')),
+            };
+        }
+        let range = selected
+            .and_then(duration_parts)
+            .or_else(|| duration_parts(DEFAULT_TIME_RANGE))
+            .unwrap_or_default();
+        TimeRange::Since(format!("now{}", date_math(&range, '-')))
+    }
+
+    /// Why the text typed into the time dropdown can't be confirmed: it is
+    /// neither a duration like `90m` or `3d12h`, a valid `from..to` range nor
+    /// matches a preset.
+    pub fn typed_time_range_error(&self) -> Option<String> {
+        let typed = self.time_filter.filter_text().trim();
+        if let Some(range) = absolute_range(typed) {
+            return range.err();
+        }
+        if typed.is_empty()
+            || duration_parts(typed).is_some()
+            || !self.time_filter.filtered_items().is_empty()
+        {
+            return None;
+        }
+        Some(format!(
+            "Invalid time range \"{}\" — type a duration like 90m or 3d12h, \
+             or a range like 2024-03-01T10:00..2024-03-01T11:30",
+            typed
+        ))
+    }
+
+    /// Confirm the time dropdown, taking a typed duration as a range of its
+    /// own and a typed `from..to` range as the exact window to search.
+    pub fn confirm_time_filter(&mut self) {
+        let typed = self.time_filter.filter_text().trim().to_string();
+        if let Some(Ok(range)) = absolute_range(&typed) {
+            self.custom_range = Some(range);
+            return;
+        }
+        self.custom_range = None;
+        if duration_parts(&typed).is_none() {
+            self.time_filter.confirm();
+            return;
+        }
+        let value = if self.anchor.is_some() { format!("±{}", typed) } else { typed };
+        self.add_time_range(&value);
+    }
+
+    /// Add `value` to the time dropdown if it isn't a preset, and select it.
+    fn add_time_range(&mut self, value: &str) {
+        if !self.time_filter.items().iter().any(|item| item == value) {
+            let mut items = self.time_filter.items().to_vec();
+            items.push(value.to_string());
+            self.time_filter.set_items(items);
+        }
+        self.time_filter.select_value(value);
+    }
+
+    /// Pin the time range presets around the selected entry's time, or go
+    /// back to ranges ending now if already pinned, and refetch.
+    pub fn toggle_anchor(&mut self) {
+        self.custom_range = None;
+        if self.anchor.is_some() {
+            self.clear_anchor();
+            self.fetch_logs();
+            self.status = "Time range is relative to now again, fetching logs...".to_string();
+            return;
+        }
+        let Some(time) = self.logs.get(self.log_index).and_then(LogEntry::time) else {
+            self.status = "The selected entry has no usable timestamp".to_string();
+            return;
+        };
+        self.anchor = Some(time);
+        self.time_filter.set_items(ANCHOR_RANGES.iter().map(|s| s.to_string()).collect());
+        self.time_filter.select_value(DEFAULT_ANCHOR_RANGE);
+        self.fetch_logs();
+        self.status = format!(
+            "Time range anchored at {}, fetching logs...",
+            time.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+
+    /// Turn the search into an exclusion, showing everything it didn't match,
+    /// and refetch. With no search, drop the exclusion instead.
+    pub fn exclude_search(&mut self) {
+        if self.search_text.is_empty() {
+            if self.exclude_text.is_empty() {
+                self.status = "Nothing to exclude — search for something first".to_string();
+                return;
+            }
+            self.exclude_text.clear();
+        } else {
+            self.exclude_text = std::mem::take(&mut self.search_text);
+        }
+        self.fetch_logs();
+    }
+
+    /// Mark or unmark the selected row. Marking a third row drops the oldest mark.
+    pub fn toggle_mark(&mut self) {
+        if self.logs.is_empty() {
+            return;
+        }
+        if let Some(pos) = self.marked.iter().position(|&i| i == self.log_index) {
+            self.marked.remove(pos);
+            return;
+        }
+        if self.marked.len() == 2 {
+            self.marked.remove(0);
+        }
+        self.marked.push(self.log_index);
+    }
+
+    /// Set the time range to exactly the span between the two marked entries
+    /// and refetch.
+    pub fn range_from_marks(&mut self) {
+        let times: Vec<DateTime<Utc>> = self
+            .marked
+            .iter()
+            .filter_map(|&i| self.logs.get(i).and_then(LogEntry::time))
+            .collect();
+        let [a, b] = times[..] else {
+            self.status = "Mark two entries with Space first".to_string();
+            return;
+        };
+        self.custom_range = Some((a.min(b), a.max(b)));
+        self.fetch_logs();
+        self.status = "Time range set from the marked entries, fetching logs...".to_string();
+    }
+
+    fn clear_anchor(&mut self) {
+        self.anchor = None;
+        self.time_filter.set_items(TIME_RANGES.iter().map(|s| s.to_string()).collect());
+        let default = self.config.defaults.time_range.as_deref().unwrap_or(DEFAULT_TIME_RANGE);
+        if !self.time_filter.select_value(default) {
+            self.time_filter.select_value(DEFAULT_TIME_RANGE);
+        }
     }
 
     pub fn selected_limit(&self) -> i64 {
@@ -117,8 +600,17 @@ impl App {
         self.search_mode_filter.selected_value() == Some("Exact")
     }
 
-    pub fn search_all_fields(&self) -> bool {
-        self.search_fields_filter.selected_value() != Some("Message only")
+    /// Document field the search is limited to, or `None` for all fields.
+    pub fn search_field(&self) -> Option<String> {
+        let fields = &self.config.fields;
+        let field = match self.search_fields_filter.selected_value()? {
+            "Message only" => &fields.message,
+            "Logger only" => &fields.logger,
+            "Thread only" => &fields.thread,
+            "Application only" => &fields.application,
+            _ => return None,
+        };
+        Some(field.clone())
     }
 
     pub fn total_pages(&self) -> u64 {
@@ -129,11 +621,24 @@ impl App {
         self.total_hits.div_ceil(limit).max(1)
     }
 
+    /// The last page that can be fetched without exceeding the result window.
+    pub fn max_reachable_page(&self) -> u64 {
+        (MAX_RESULT_WINDOW / self.selected_limit().max(1) as u64).max(1)
+    }
+
+    fn result_window_message() -> String {
+        format!(
+            "Cannot page past {} results — narrow the time range or filters",
+            MAX_RESULT_WINDOW
+        )
+    }
+
     pub fn active_filter_mut(&mut self) -> &mut FilterField {
         match self.focused {
             Pane::Profile => &mut self.profile_filter,
             Pane::Application => &mut self.app_filter,
             Pane::Severity => &mut self.severity_filter,
+            Pane::Thread => &mut self.thread_filter,
             Pane::TimeRange => &mut self.time_filter,
             Pane::Limit => &mut self.limit_filter,
             Pane::SearchMode => &mut self.search_mode_filter,
@@ -142,10 +647,92 @@ impl App {
         }
     }
 
+    /// Fill the application dropdown with `ALL`, then the favorites, then
+    /// the remaining applications below a separator.
+    fn set_app_items(&mut self, applications: Vec<String>) {
+        let favorites = &self.config.favorite_apps;
+        let mut items = vec![ALL.to_string()];
+        items.extend(favorites.iter().cloned());
+        items.extend(applications.into_iter().filter(|a| !favorites.contains(a)));
+        let pinned = if favorites.is_empty() { 0 } else { 1 + favorites.len() };
+        self.app_filter.set_pinned_items(items, pinned);
+    }
+
+    /// Add the highlighted item of the open dropdown to those picked
+    /// together, or take it out. Picking ALL goes back to a single value.
+    pub fn toggle_checked(&mut self) {
+        let field = self.active_filter_mut();
+        if field.highlighted_value() == Some(ALL) {
+            field.clear_checked();
+        } else {
+            field.toggle_highlighted();
+        }
+    }
+
+    /// Switch between showing only the selected severities and leaving them out.
+    pub fn toggle_severity_exclude(&mut self) {
+        self.severity_exclude = !self.severity_exclude;
+    }
+
+    /// Add or remove the highlighted application from the favorites.
+    pub fn toggle_favorite_app(&mut self) {
+        let Some(app) = self.app_filter.highlighted_value().map(str::to_owned) else {
+            return;
+        };
+        if app == ALL {
+            return;
+        }
+
+        let favorites = &mut self.config.favorite_apps;
+        let added = match favorites.iter().position(|f| *f == app) {
+            Some(pos) => {
+                favorites.remove(pos);
+                false
+            }
+            None => {
+                favorites.push(app.clone());
+                true
+            }
+        };
+
+        let selected = self.app_filter.selected_value().map(str::to_owned);
+        let checked: Vec<String> =
+            self.app_filter.checked_values().into_iter().map(str::to_owned).collect();
+        let mut applications: Vec<String> = self
+            .app_filter
+            .items()
+            .iter()
+            .filter(|a| *a != ALL)
+            .cloned()
+            .collect();
+        applications.sort();
+        self.set_app_items(applications);
+        if let Some(selected) = selected {
+            self.app_filter.select_value(&selected);
+        }
+        for app in &checked {
+            self.app_filter.check_value(app);
+        }
+        self.app_filter.highlight_value(&app);
+
+        self.status = match config::save_config(&self.config) {
+            Ok(_) if added => format!("Added {} to favorites", app),
+            Ok(_) => format!("Removed {} from favorites", app),
+            Err(e) => format!("Failed to save config: {}", e),
+        };
+    }
+
     pub async fn load_filters(&mut self) {
         self.status = "Fetching available filters...".to_string();
 
-        match opensearch::fetch_available_filters(&self.config.endpoint_url, &self.config.aws_region).await {
+        let cluster = self.cluster();
+        let filters = async {
+            let client = self.client.get(&cluster).await?;
+            let (fields, severity_field) = (&self.config.fields, &self.config.severity_field);
+            opensearch::fetch_available_filters(&client, &cluster.indices, fields, severity_field)
+                .await
+        };
+        match filters.await {
             Ok(filters) => {
 
                 self.status = format!(
@@ -153,38 +740,55 @@ impl App {
                     filters.environments.len(),
                     filters.applications.len()
                 );
+                if let Some(note) = filters.note {
+                    self.status.push_str(&format!(" ({})", note));
+                }
                 let environments: Vec<String> = filters.environments.into_iter()
                     .filter(|e| e != "ACTIVE_PROFILE_IS_UNDEFINED")
                     .collect();
                 self.profile_filter.set_items(environments);
-                self.profile_filter.select_value("production");
+                self.profile_filter.select_value(DEFAULT_PROFILE);
 
-                let mut applications = vec![ALL.to_string()];
-                applications.extend(
-                    filters.applications.into_iter()
-                        .filter(|a| a != "APPLICATION_NAME_IS_UNDEFINED"),
-                );
-                self.app_filter.set_items(applications);
+                let applications: Vec<String> = filters.applications.into_iter()
+                    .filter(|a| a != "APPLICATION_NAME_IS_UNDEFINED")
+                    .collect();
+                self.set_app_items(applications);
 
                 let mut severities = vec![ALL.to_string()];
-                severities.extend(filters.severities);
+                // Several raw values may share a label; keep its first place
+                let mut seen = HashSet::new();
+                severities.extend(
+                    filters
+                        .severities
+                        .iter()
+                        .map(|raw| self.config.severity_label(raw))
+                        .filter(|label| seen.insert(label.clone())),
+                );
                 self.severity_filter.set_items(severities);
 
-                let time_ranges: Vec<String> =
-                    ["1m", "5m", "15m", "30m", "1h", "3h", "6h", "12h", "24h", "3d", "7d"]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect();
+                let mut threads = vec![ALL.to_string()];
+                threads.extend(filters.threads);
+                self.thread_filter.set_items(threads);
+
+                let time_ranges: Vec<String> = TIME_RANGES.iter().map(|s| s.to_string()).collect();
                 self.time_filter.set_items(time_ranges);
-                self.time_filter.select_value("5m");
+                self.time_filter.select_value(DEFAULT_TIME_RANGE);
 
                 let limits: Vec<String> = ["50", "100", "200", "500", "1000"]
                     .iter()
                     .map(|s| s.to_string())
                     .collect();
                 self.limit_filter.set_items(limits);
-                self.limit_filter.select_value("50");
+                self.limit_filter.select_value(DEFAULT_LIMIT);
+
+                let defaults = self.config.defaults.clone();
+                let missing = self.apply_filter_state(&defaults);
+                if !missing.is_empty() {
+                    self.notice = Some(format!("saved defaults not available: {}", missing.join(", ")));
+                }
+                self.apply_last_filters();
             }
+            Err(e) if opensearch::is_expired_credentials(&e) => self.report_expired_credentials(),
             Err(e) => {
 
                 self.status = format!("Error loading filters: {}", e);
@@ -192,68 +796,1652 @@ impl App {
         }
     }
 
-    pub async fn fetch_logs(&mut self) {
-        self.fetch_page(1).await;
+    pub fn fetch_logs(&mut self) {
+        self.fetch_page(1);
     }
 
-    pub async fn fetch_page(&mut self, page: u64) {
-        let Some(env) = self.selected_env().map(str::to_owned) else {
+    /// Switch to the time range configured for the selected severity, or back
+    /// to the default one, unless the time range was picked by hand.
+    pub fn apply_severity_time_range(&mut self) {
+        if self.time_overridden || self.anchor.is_some() || self.custom_range.is_some() {
+            return;
+        }
+        let severity = match self.selected_severities().as_slice() {
+            [severity] if !self.severity_exclude => *severity,
+            _ => ALL,
+        };
+        let range = self
+            .config
+            .severity_time_ranges
+            .get(severity)
+            .or(self.config.defaults.time_range.as_ref())
+            .map_or(DEFAULT_TIME_RANGE, String::as_str)
+            .to_owned();
+        if duration_parts(&range).is_some() {
+            self.add_time_range(&range);
+        }
+    }
+
+    /// The current filter selections.
+    pub fn filter_state(&self) -> FilterState {
+        let value = |field: &FilterField| field.selected_value().map(str::to_owned);
+        FilterState {
+            profile: value(&self.profile_filter),
+            application: value(&self.app_filter),
+            applications: self.app_filter.checked_values().into_iter().map(str::to_owned).collect(),
+            severity: value(&self.severity_filter),
+            severities: self.severity_filter.checked_values().into_iter().map(str::to_owned).collect(),
+            severity_exclude: self.severity_exclude,
+            thread: value(&self.thread_filter),
+            time_range: value(&self.time_filter),
+            limit: value(&self.limit_filter),
+            search: Some(self.search_text.clone()).filter(|s| !s.is_empty()),
+            exclude: Some(self.exclude_text.clone()).filter(|s| !s.is_empty()),
+            search_mode: value(&self.search_mode_filter),
+            search_fields: value(&self.search_fields_filter),
+            sort: Some(self.sort),
+            pivot: self.pivot.clone(),
+        }
+    }
+
+    /// The selections remembered in `last_filters` for the next start, or
+    /// `None` while the filters haven't been loaded.
+    pub fn last_filters(&self) -> Option<FilterState> {
+        if self.profile_filter.items().is_empty() {
+            return None;
+        }
+        let state = self.filter_state();
+        // A range around an anchor or between marks means nothing on its own
+        let pinned = self.anchor.is_some() || self.custom_range.is_some();
+        Some(FilterState {
+            profile: state.profile,
+            application: state.application,
+            applications: state.applications,
+            severity: state.severity,
+            severities: state.severities,
+            severity_exclude: state.severity_exclude,
+            time_range: state.time_range.filter(|_| !pinned),
+            limit: state.limit,
+            ..FilterState::default()
+        })
+    }
+
+    /// Select the filters in use when the app last exited. Values no longer
+    /// available keep the default selection.
+    fn apply_last_filters(&mut self) {
+        let mut last = self.config.last_filters.clone();
+        // Only part of a multiple selection would show something else entirely
+        if !last.applications.iter().all(|app| self.app_filter.items().contains(app)) {
+            last.application = None;
+            last.applications.clear();
+        }
+        if !last.severities.iter().all(|severity| self.severity_filter.items().contains(severity)) {
+            last.severity = None;
+            last.severities.clear();
+            last.severity_exclude = self.severity_exclude;
+        }
+        self.apply_filter_state(&last);
+    }
+
+    /// Where the user is now, to pick up from on the next launch.
+    pub fn session(&self) -> Session {
+        Session {
+            filters: self.filter_state(),
+            page: self.page,
+            log_index: self.log_index,
+            grouped: self.grouped,
+            find_text: self.find_text.clone(),
+            saved_at: Utc::now().timestamp(),
+        }
+    }
+
+    /// Go back to a saved session and fetch its page. Returns the filter
+    /// values that are no longer available.
+    pub fn restore_session(&mut self, session: &Session) -> Vec<String> {
+        let missing = self.apply_filter_state(&session.filters);
+        self.grouped = session.grouped;
+        self.find_text = session.find_text.clone();
+        self.restore_index = Some(session.log_index);
+        self.fetch_page(session.page.max(1));
+        missing
+    }
+
+    /// Select the values in `state`, leaving filters it doesn't set alone.
+    /// Returns the values that aren't available, as `filter=value`.
+    pub fn apply_filter_state(&mut self, state: &FilterState) -> Vec<String> {
+        let mut missing = Vec::new();
+        if let Some(range) = &state.time_range
+            && duration_parts(range.trim_start_matches('±')).is_some()
+        {
+            self.add_time_range(range);
+        }
+        for (name, field, value) in [
+            ("profile", &mut self.profile_filter, &state.profile),
+            ("application", &mut self.app_filter, &state.application),
+            ("severity", &mut self.severity_filter, &state.severity),
+            ("thread", &mut self.thread_filter, &state.thread),
+            ("time_range", &mut self.time_filter, &state.time_range),
+            ("limit", &mut self.limit_filter, &state.limit),
+            ("search_mode", &mut self.search_mode_filter, &state.search_mode),
+            ("search_fields", &mut self.search_fields_filter, &state.search_fields),
+        ] {
+            if let Some(value) = value
+                && !field.select_value(value)
+            {
+                missing.push(format!("{}={}", name, value));
+            }
+        }
+        for app in &state.applications {
+            if !self.app_filter.check_value(app) {
+                missing.push(format!("application={}", app));
+            }
+        }
+        for severity in &state.severities {
+            if !self.severity_filter.check_value(severity) {
+                missing.push(format!("severity={}", severity));
+            }
+        }
+        self.severity_exclude = state.severity_exclude;
+        if let Some(search) = &state.search {
+            self.search_text = search.clone();
+        }
+        if let Some(exclude) = &state.exclude {
+            self.exclude_text = exclude.clone();
+        }
+        if let Some(sort) = state.sort {
+            self.sort = sort;
+        }
+        if let Some(pivot) = &state.pivot {
+            self.pivot = Some(pivot.clone());
+        }
+        missing
+    }
+
+    /// Make the current filters and detail mode the startup defaults.
+    pub fn save_defaults(&mut self) {
+        self.store_defaults();
+        self.status = match config::save_config(&self.config) {
+            Ok(_) => "Saved the current view as the startup default".to_string(),
+            Err(e) => format!("Failed to save config: {}", e),
+        };
+    }
+
+    /// Copy the current filters, detail mode and view toggles into the config
+    /// as what the next start opens with.
+    fn store_defaults(&mut self) {
+        self.config.defaults = self.filter_state();
+        self.config.detail_mode = self.detail_mode;
+        self.config.dense_rows = self.dense;
+        self.config.two_line_rows = self.two_line;
+        self.config.relative_timestamps = self.relative_time;
+        self.config.show_trace_id = self.show_trace_id;
+    }
+
+    /// Reset every filter and the search to its default and refetch from page 1.
+    pub fn reset_filters(&mut self) {
+        self.profile_filter.select_first();
+        self.profile_filter.select_value(DEFAULT_PROFILE);
+        self.app_filter.select_first();
+        self.severity_filter.select_first();
+        self.severity_exclude = false;
+        self.thread_filter.select_first();
+        self.time_filter.select_value(DEFAULT_TIME_RANGE);
+        self.limit_filter.select_value(DEFAULT_LIMIT);
+        self.search_text.clear();
+        self.exclude_text.clear();
+        self.search_mode_filter.select_first();
+        self.search_fields_filter.select_first();
+        self.sort = SortOrder::default();
+        self.time_overridden = false;
+        self.custom_range = None;
+        self.raw_query = None;
+        self.pivot = None;
+        if self.anchor.is_some() {
+            self.clear_anchor();
+        }
+        let defaults = self.config.defaults.clone();
+        self.apply_filter_state(&defaults);
+
+        self.fetch_logs();
+        self.status = "Filters reset to defaults, fetching logs...".to_string();
+    }
 
+    /// Start fetching `page` in the background. The result is applied by
+    /// [`App::poll_fetches`] once it arrives; responses to requests that have
+    /// since been superseded are discarded.
+    pub fn fetch_page(&mut self, page: u64) {
+        let Some(env) = self.selected_env().map(str::to_owned) else {
             self.status = "No environment selected".to_string();
             return;
         };
+        if page > self.max_reachable_page() {
+            self.status = Self::result_window_message();
+            return;
+        }
+        self.rerun_total = None;
 
-        let app = self.selected_app().map(str::to_owned);
-        let severity = self.selected_severity().map(str::to_owned);
-        let time_range = self.selected_time_range().to_owned();
+        let query = self.log_query(env, page);
+        // Paging keeps the same filters; anything else may have changed them
+        if page == 1 || self.histogram_query.as_ref() != Some(&histogram_query(&query)) {
+            self.fetch_histogram(&query);
+        }
+        let app_label = match query.applications.as_slice() {
+            [] => ALL.to_string(),
+            apps => apps.join(", "),
+        };
+        let label = match self.selected_severities().as_slice() {
+            [] => format!("{} ({})", app_label, query.profile),
+            sevs if self.severity_exclude => {
+                format!("{} ({}) [not {}]", app_label, query.profile, sevs.join(", "))
+            }
+            sevs => format!("{} ({}) [{}]", app_label, query.profile, sevs.join(", ")),
+        };
+        let label = if query.raw_body.is_some() {
+            format!("raw query on {}", query.profile)
+        } else {
+            label
+        };
+
+        self.fetch_seq += 1;
+        // This fetch already uses the latest search text
+        self.search_edited_at = None;
+        // At most one request at a time; the older one's result would be dropped anyway
+        if let Some(task) = self.fetch_task.take() {
+            task.abort();
+        }
+
+        if let Some(result) = self.cached_page(&query) {
+            // Supersedes anything still in flight
+            self.pending = false;
+            self.apply_result(page, &label, query, result);
+            self.status.push_str(" (cached)");
+            return;
+        }
+
+        self.status = format!("Fetching page {} from {}... (Esc to cancel)", page, label);
+        self.pending = true;
+        let seq = self.fetch_seq;
+        let tx = self.fetch_tx.clone();
+        let cluster = self.cluster();
+        let shared = self.client.clone();
+        self.fetch_task = Some(tokio::spawn(async move {
+            let result = match shared.get(&cluster).await {
+                Ok(client) => opensearch::fetch_logs(&client, &cluster.indices, &query).await,
+                Err(e) => Err(e),
+            };
+            // The receiver only goes away when the app is shutting down
+            let _ = tx.send(FetchOutcome { seq, page, label, query, result });
+        }));
+    }
+
+    /// Start fetching the log volume across the time range of `query` in
+    /// the background. The result is applied by [`App::poll_fetches`].
+    fn fetch_histogram(&mut self, query: &LogQuery) {
+        if !self.config.show_histogram {
+            return;
+        }
+        if let Some(task) = self.histogram_task.take() {
+            task.abort();
+        }
+        let query = histogram_query(query);
+        self.histogram_query = Some(query.clone());
+        let span = self.time_span_secs();
+        let interval = HISTOGRAM_INTERVALS
+            .iter()
+            .copied()
+            .find(|interval| {
+                let secs = duration_parts(interval).map_or(0, |parts| duration_secs(&parts));
+                span / secs <= HISTOGRAM_BUCKETS
+            })
+            .unwrap_or(HISTOGRAM_INTERVALS[HISTOGRAM_INTERVALS.len() - 1]);
+        let tx = self.histogram_tx.clone();
+        let cluster = self.cluster();
+        let shared = self.client.clone();
+        self.histogram_task = Some(tokio::spawn(async move {
+            let result = match shared.get(&cluster).await {
+                Ok(client) => {
+                    opensearch::fetch_histogram(&client, &cluster.indices, &query, interval).await
+                }
+                Err(e) => Err(e),
+            };
+            let _ = tx.send(HistogramOutcome { query, interval, result });
+        }));
+    }
+
+    /// Length of the selected time range in seconds.
+    fn time_span_secs(&self) -> i64 {
+        if let Some((from, to)) = self.custom_range {
+            return (to - from).num_seconds();
+        }
+        let selected = self.time_filter.selected_value();
+        if self.anchor.is_some() {
+            let span = selected
+                .and_then(|v| v.strip_prefix('±'))
+                .and_then(duration_parts)
+                .or_else(|| duration_parts(DEFAULT_ANCHOR_RANGE.trim_start_matches('±')))
+                .unwrap_or_default();
+            return 2 * duration_secs(&span);
+        }
+        let range = selected
+            .and_then(duration_parts)
+            .or_else(|| duration_parts(DEFAULT_TIME_RANGE))
+            .unwrap_or_default();
+        duration_secs(&range)
+    }
+
+    /// The query for `page` of the current filters in environment `profile`.
+    fn log_query(&self, profile: String, page: u64) -> LogQuery {
         let limit = self.selected_limit();
-        let from = (page - 1) as i64 * limit;
-        let app_label = app.as_deref().unwrap_or("ALL");
-        let label = match &severity {
-            Some(sev) => format!("{} ({}) [{}]", app_label, env, sev),
-            None => format!("{} ({})", app_label, env),
-        };
-        let search = if self.search_text.is_empty() { None } else { Some(self.search_text.as_str()) };
-        let search_exact = self.search_exact();
-        let search_all_fields = self.search_all_fields();
-        self.status = format!("Fetching page {} from {}...", page, label);
-        match opensearch::fetch_logs(&self.config.endpoint_url, &self.config.aws_region, app.as_deref(), &env, severity.as_deref(), &time_range, search, search_exact, search_all_fields, limit, from).await
-        {
-            Ok(result) => {
+        let severities: Vec<String> = self
+            .selected_severities()
+            .into_iter()
+            .map(|label| self.config.severity_raw(label).to_owned())
+            .collect();
+        let (severities, exclude_severities) = if self.severity_exclude {
+            (Vec::new(), severities)
+        } else {
+            (severities, Vec::new())
+        };
+        LogQuery {
+            profile,
+            applications: self.selected_apps().into_iter().map(str::to_owned).collect(),
+            severities,
+            exclude_severities,
+            severity_field: self.config.severity_field.clone(),
+            fields: self.config.fields.clone(),
+            thread: self.selected_thread().map(str::to_owned),
+            time_range: self.selected_time_range(),
+            search: Some(self.search_text.clone()).filter(|s| !s.is_empty()),
+            exclude: Some(self.exclude_text.clone()).filter(|s| !s.is_empty()),
+            search_exact: self.search_exact(),
+            search_field: self.search_field(),
+            pivot: self.pivot.clone(),
+            sort: self.sort,
+            noise: self.config.noise.clone(),
+            size: limit,
+            from: (page - 1) as i64 * limit,
+            raw_body: self.raw_query.clone(),
+        }
+    }
+
+    /// The search body sent for the current page.
+    pub fn query_body(&self) -> Option<Value> {
+        let env = self.selected_env()?.to_owned();
+        Some(opensearch::search_body(&self.log_query(env, self.page)))
+    }
+
+    pub fn open_query_debug(&mut self) {
+        if self.selected_env().is_some() {
+            self.query_scroll = 0;
+            self.focused = Pane::QueryDebug;
+        }
+    }
+
+    /// Search with a body edited by hand from now on, or go back to the
+    /// filters if `text` is blank. Invalid JSON is reported and ignored.
+    pub fn apply_raw_query(&mut self, text: &str) {
+        if text.trim().is_empty() {
+            self.raw_query = None;
+            self.fetch_logs();
+            self.status = "Back to the filters, fetching logs...".to_string();
+            return;
+        }
+        match serde_json::from_str::<Value>(text) {
+            Ok(body) if body.is_object() => {
+                self.raw_query = Some(body);
+                self.fetch_logs();
+            }
+            Ok(_) => self.status = "Invalid query: the body must be a JSON object".to_string(),
+            Err(e) => self.status = format!("Invalid query JSON: {}", e),
+        }
+    }
+
+    /// Link to the current query in Dashboards Discover, if `dashboards_url`
+    /// is configured.
+    pub fn discover_url(&self) -> Option<String> {
+        let base = self.config.dashboards_url.as_deref()?;
+        let env = self.selected_env()?.to_owned();
+        let query = self.log_query(env, self.page);
+        Some(dashboards::discover_url(
+            base,
+            &query,
+            self.config.dashboards_index_pattern.as_deref(),
+        ))
+    }
+
+    /// Start counting the distinct values of the field typed into the
+    /// prompt across all entries matching the current filters. The result is
+    /// shown by [`App::poll_fetches`].
+    pub fn count_distinct(&mut self) {
+        let field = self.distinct_field.trim().to_string();
+        if field.is_empty() {
+            return;
+        }
+        let Some(env) = self.selected_env().map(str::to_owned) else {
+            self.status = "No environment selected".to_string();
+            return;
+        };
+        if let Some(task) = self.distinct_task.take() {
+            task.abort();
+        }
+        self.status = format!("Counting distinct {} values...", field);
+        let query = self.log_query(env, 1);
+        let tx = self.distinct_tx.clone();
+        let cluster = self.cluster();
+        let shared = self.client.clone();
+        self.distinct_task = Some(tokio::spawn(async move {
+            let result = match shared.get(&cluster).await {
+                Ok(client) => opensearch::count_distinct(&client, &cluster.indices, &query, &field).await,
+                Err(e) => Err(e),
+            };
+            let _ = tx.send(DistinctOutcome { field, result });
+        }));
+    }
+
+    /// Build a new client on the next request, loading the credentials again.
+    pub fn reconnect(&mut self) {
+        self.client.reset();
+    }
+
+    /// Refetch the current page, dropping any cached pages.
+    pub fn refresh(&mut self) {
+        self.page_cache.clear();
+        self.fetch_page(self.page);
+    }
+
+    /// Run the current query again from page 1, bypassing the page cache,
+    /// and report how the hit count changed.
+    pub fn rerun(&mut self) {
+        let total = self.total_hits;
+        self.page_cache.clear();
+        self.fetch_page(1);
+        if self.pending {
+            self.rerun_total = Some(total);
+        }
+    }
+
+    /// Turn following the newest entry on or off.
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        self.followed_new = 0;
+        if self.follow {
+            self.log_index = 0;
+            self.new_entries = 0;
+            self.status = "Following the newest entry".to_string();
+        } else {
+            self.status = "No longer following the newest entry".to_string();
+        }
+    }
 
-                self.status = format!("Loaded {} logs from {}", result.logs.len(), label);
-                self.total_hits = result.total;
-                self.page = page;
-                self.logs = result.logs;
+    /// Abort the fetch in flight, keeping the entries already shown.
+    pub fn cancel_fetch(&mut self) {
+        if let Some(task) = self.fetch_task.take() {
+            task.abort();
+        }
+        // A result already sent is dropped as stale
+        self.fetch_seq += 1;
+        self.pending = false;
+        self.status = "Fetch cancelled".to_string();
+    }
+
+    /// Turn live tail on or off. Tailing jumps to the newest entries.
+    pub fn toggle_live_tail(&mut self) {
+        self.live_tail = !self.live_tail;
+        self.tail_paused = false;
+        if self.live_tail {
+            // New entries arrive at the end of an oldest-first list
+            if self.sort == SortOrder::Oldest {
+                self.sort = SortOrder::Time;
+            }
+            self.tailed_at = Instant::now();
+            self.page_cache.clear();
+            self.fetch_page(1);
+        } else {
+            self.status = "Live tail off".to_string();
+        }
+    }
+
+    /// Apply any background fetch results that have arrived.
+    pub fn poll_fetches(&mut self) {
+        while let Ok(outcome) = self.histogram_rx.try_recv() {
+            if self.histogram_query.as_ref() == Some(&outcome.query) {
+                // Without a histogram the table simply gets the room
+                self.histogram = outcome.result.unwrap_or_default();
+                self.histogram_interval = outcome.interval;
+            }
+        }
+        while let Ok(outcome) = self.distinct_rx.try_recv() {
+            self.status = match outcome.result {
+                Ok(count) => {
+                    format!("{} distinct {} values in the current query", count, outcome.field)
+                }
+                Err(e) => format!("Error counting distinct {} values: {}", outcome.field, e),
+            };
+        }
+        while let Ok(outcome) = self.fetch_rx.try_recv() {
+            if outcome.seq != self.fetch_seq {
+                continue;
+            }
+            self.pending = false;
+            match outcome.result {
+                Ok(result) => {
+                    self.cache_page(outcome.query.clone(), &result);
+                    self.apply_result(outcome.page, &outcome.label, outcome.query, result);
+                }
+                Err(e) if e.to_string().contains("Result window is too large") => {
+                    self.status = Self::result_window_message();
+                }
+                Err(e) if opensearch::is_expired_credentials(&e) => self.report_expired_credentials(),
+                Err(e) => {
+                    self.status = format!("Error: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Stop live tail from retrying and ask the user to log in again.
+    fn report_expired_credentials(&mut self) {
+        self.client.reset();
+        self.credentials_expired = true;
+        self.status =
+            "AWS credentials expired — log in again (e.g. aws sso login), then press R to retry"
+                .to_string();
+    }
+
+    fn apply_result(&mut self, page: u64, label: &str, query: LogQuery, result: LogResult) {
+        self.credentials_expired = false;
+        self.status = if result.malformed > 0 {
+            format!(
+                "Loaded {} of {} logs from {} ({} malformed)",
+                result.logs.len(),
+                result.logs.len() + result.malformed,
+                label,
+                result.malformed
+            )
+        } else {
+            format!("Loaded {} logs from {}", result.logs.len(), label)
+        };
+        if let Some(note) = result.note {
+            self.status.push_str(&format!(" ({})", note));
+        }
+        if let Some(previous) = self.rerun_total.take() {
+            let change = result.total as i64 - previous as i64;
+            self.status.push_str(&format!(" — {:+} hits vs previous run", change));
+        }
+        if let Some(notice) = self.notice.take() {
+            self.status.push_str(&format!(" — {}", notice));
+        }
+        self.total_hits = result.total;
+        // Live tail follows the newest entry unless the user scrolled down,
+        // in which case the selected entry is held in place.
+        let hold = self.live_tail && !self.follow && page == 1 && self.page == 1 && self.log_index > 0;
+        self.page = page;
+        let previous = std::mem::replace(&mut self.logs, result.logs);
+        for log in &mut self.logs {
+            if let Some(raw) = log
+                .field(&self.config.severity_field)
+                .and_then(opensearch::scalar_to_string)
+            {
+                log.severity = self.config.severity_label(&raw);
+            }
+        }
+        if query.sort == SortOrder::Severity {
+            // Stable, so entries stay newest first within each severity
+            self.logs.sort_by_key(|log| severity_rank(&log.severity));
+        }
+        self.severity_counts = severity_breakdown(&self.logs);
+        self.toggled_stacktraces.clear();
+        self.marked.clear();
+        self.group_cursor = 0;
+
+        // Highlight what a refresh brought in, but not a whole new result set
+        let refreshed = self.shown_query.as_ref() == Some(&query) && !previous.is_empty();
+        self.flashed_rows = if refreshed {
+            self.logs
+                .iter()
+                .enumerate()
+                .filter(|(_, log)| !previous.iter().any(|old| old.source == log.source))
+                .map(|(index, _)| index)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        self.flashed_at = Instant::now();
+        self.shown_query = Some(query);
+
+        let position = |entry: Option<&LogEntry>| {
+            entry.and_then(|entry| self.logs.iter().position(|log| log.source == entry.source))
+        };
+        match (hold, position(previous.get(self.log_index))) {
+            (true, Some(index)) => {
+                self.new_entries += position(previous.first()).unwrap_or(0);
+                self.log_index = index;
+            }
+            _ => {
+                self.new_entries = 0;
                 self.log_index = 0;
-                self.focused = Pane::Logs;
             }
-            Err(e) => {
+        }
+        if self.live_tail && refreshed && page == 1 {
+            let arrived = position(previous.first()).unwrap_or(0);
+            if arrived > 0 {
+                if std::mem::take(&mut self.looked) {
+                    self.unseen_entries = 0;
+                }
+                self.unseen_entries = (self.unseen_entries + arrived).min(self.logs.len());
+            }
+        } else {
+            self.unseen_entries = 0;
+        }
+        self.followed_new = if self.follow && refreshed && page == 1 {
+            position(previous.first()).unwrap_or(0)
+        } else {
+            0
+        };
+        if let Some(index) = self.restore_index.take() {
+            self.log_index = index.min(self.logs.len().saturating_sub(1));
+        }
+        self.select_visible_row();
+    }
+
+    /// A cached result for `query` that hasn't outlived the cache TTL.
+    fn cached_page(&mut self, query: &LogQuery) -> Option<LogResult> {
+        let ttl = Duration::from_secs(self.config.page_cache_ttl_secs);
+        self.page_cache.retain(|cached| cached.fetched_at.elapsed() < ttl);
+        self.page_cache
+            .iter()
+            .find(|cached| cached.query == *query)
+            .map(|cached| cached.result.clone())
+    }
+
+    fn cache_page(&mut self, query: LogQuery, result: &LogResult) {
+        let capacity = self.config.page_cache_size;
+        if capacity == 0 {
+            return;
+        }
+        self.page_cache.retain(|cached| cached.query != query);
+        while self.page_cache.len() >= capacity {
+            self.page_cache.pop_front();
+        }
+        self.page_cache.push_back(CachedPage {
+            query,
+            fetched_at: Instant::now(),
+            result: result.clone(),
+        });
+    }
+
+    /// Record a search-text edit so live search can re-run the query once
+    /// typing pauses.
+    pub fn search_edited(&mut self) {
+        self.search_history_index = None;
+        if self.config.live_search {
+            self.search_edited_at = Some(Instant::now());
+        }
+    }
+
+    /// Add the search text to the history, unless it is empty or the same
+    /// as the last entry.
+    pub fn record_search(&mut self) {
+        self.search_history_index = None;
+        if self.search_text.is_empty() || self.search_history.last() == Some(&self.search_text) {
+            return;
+        }
+        self.search_history.push(self.search_text.clone());
+        let excess = self.search_history.len().saturating_sub(SEARCH_HISTORY_LEN);
+        self.search_history.drain(..excess);
+    }
+
+    /// Put the previous (`older`) or next past search into the search box.
+    /// Going past the newest entry brings back what was typed.
+    pub fn recall_search(&mut self, older: bool) {
+        let index = match (self.search_history_index, older) {
+            (None, true) if !self.search_history.is_empty() => {
+                self.search_draft = self.search_text.clone();
+                Some(self.search_history.len() - 1)
+            }
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.search_history.len() => Some(index + 1),
+            (Some(_), false) => None,
+            (None, _) => return,
+        };
+        self.search_text = match index {
+            Some(index) => self.search_history[index].clone(),
+            None => std::mem::take(&mut self.search_draft),
+        };
+        self.search_edited();
+        self.search_history_index = index;
+    }
 
-                self.status = format!("Error: {}", e);
+    /// Fire the debounced live search once the user has stopped typing and
+    /// the search text differs from the shown results.
+    pub fn tick(&mut self) {
+        if let Some(edited_at) = self.search_edited_at
+            && edited_at.elapsed() >= LIVE_SEARCH_DEBOUNCE
+        {
+            self.search_edited_at = None;
+            // Typing and deleting back to the shown search needs no request
+            let shown = self.shown_query.as_ref().map(|q| q.search.as_deref().unwrap_or(""));
+            if self.pending || shown != Some(self.search_text.as_str()) {
+                self.fetch_logs();
             }
         }
+        let idle_limit = Duration::from_secs(self.config.live_tail_idle_mins * 60);
+        if self.live_tail
+            && !self.tail_paused
+            && !idle_limit.is_zero()
+            && self.last_input.elapsed() >= idle_limit
+        {
+            self.tail_paused = true;
+            self.status = "Live tail paused after inactivity — press any key to resume".to_string();
+        }
+        let interval = Duration::from_secs(self.config.live_tail_interval_secs.max(1));
+        if self.live_tail
+            && !self.tail_paused
+            && !self.credentials_expired
+            && self.page == 1
+            && !self.pending
+            && self.tailed_at.elapsed() >= interval
+        {
+            self.tailed_at = Instant::now();
+            self.refresh();
+        }
+    }
+
+    /// Note a key press. Returns true if it resumed a paused live tail, in
+    /// which case the key shouldn't do anything else.
+    pub fn key_pressed(&mut self) -> bool {
+        self.last_input = Instant::now();
+        self.looked = true;
+        if !self.tail_paused {
+            return false;
+        }
+        self.tail_paused = false;
+        self.tailed_at = Instant::now();
+        self.refresh();
+        true
     }
 
-    pub async fn next_page(&mut self) {
-        if self.page < self.total_pages() {
-            self.fetch_page(self.page + 1).await;
+    pub fn next_page(&mut self) {
+        if self.page >= self.total_pages() {
+            return;
+        }
+        if self.page >= self.max_reachable_page() {
+            self.status = Self::result_window_message();
+            return;
         }
+        self.fetch_page(self.page + 1);
     }
 
-    pub async fn prev_page(&mut self) {
+    /// Hide the selected entry's logger from now on, saving it to the noise list.
+    pub fn mute_selected_logger(&mut self) {
+        let Some(logger) = self.logs.get(self.log_index).map(|log| log.logger.clone()) else {
+            return;
+        };
+        if logger.is_empty() {
+            self.status = "This entry has no logger to mute".to_string();
+            return;
+        }
+        self.config.noise.push(config::NoiseFilter {
+            logger: Some(logger.clone()),
+            message: None,
+        });
+        if let Err(e) = config::save_config(&self.config) {
+            self.status = format!("Failed to save config: {}", e);
+            return;
+        }
+        self.refresh();
+        self.status = format!("Muted {}, fetching logs...", logger);
+    }
+
+    /// Move to the next sort order and refetch. Live tail skips oldest first.
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        if self.live_tail && self.sort == SortOrder::Oldest {
+            self.sort = self.sort.next();
+        }
+        self.fetch_logs();
+        self.status = format!("Sorting {}, fetching logs...", self.sort.label());
+    }
+
+    /// Why context menu option `index` doesn't apply to the selected entry,
+    /// if it doesn't.
+    pub fn context_option_unavailable(&self, index: usize) -> Option<String> {
+        let log = self.logs.get(self.log_index)?;
+        match CONTEXT_MENU_OPTIONS.get(index) {
+            Some(&"Copy trace ID" | &"Open trace") if log.trace_id.is_none() => {
+                Some("This entry has no trace ID".to_string())
+            }
+            Some(&"Open trace") if self.config.trace_url_template.is_none() => {
+                Some("Set trace_url_template in the config to open traces".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Link to the selected entry's trace, from `trace_url_template`.
+    pub fn trace_url(&self) -> Option<String> {
+        let template = self.config.trace_url_template.as_ref()?;
+        let trace_id = self.logs.get(self.log_index)?.trace_id.as_ref()?;
+        Some(template.replace("{traceId}", trace_id))
+    }
+
+    /// Whether the relevance score column is shown.
+    pub fn show_score(&self) -> bool {
+        self.config.show_score || self.sort == SortOrder::Score
+    }
+
+    /// Jump to the page typed into the goto prompt.
+    pub fn goto_page(&mut self) {
+        let input = std::mem::take(&mut self.goto_input);
+        let total = self.total_pages();
+        match input.parse::<u64>() {
+            Ok(page) if (1..=total).contains(&page) => {
+                if page > self.max_reachable_page() {
+                    self.status = Self::result_window_message();
+                } else {
+                    self.fetch_page(page);
+                }
+            }
+            _ => self.status = format!("No page {} (1-{})", input, total),
+        }
+    }
+
+    pub fn prev_page(&mut self) {
         if self.page > 1 {
-            self.fetch_page(self.page - 1).await;
+            self.fetch_page(self.page - 1);
         }
     }
 
     pub fn scroll_down(&mut self) {
-        if !self.logs.is_empty() {
-            self.log_index = (self.log_index + 1).min(self.logs.len() - 1);
+        if self.grouped {
+            let last = self.group_rows().len().saturating_sub(1);
+            self.select_group_row((self.group_cursor + 1).min(last));
+        } else if !self.logs.is_empty() {
+            let before = self.log_index;
+            self.log_index = (self.log_index + 1..self.logs.len())
+                .find(|&i| self.row_visible(&self.logs[i]))
+                .unwrap_or(before);
+            self.clear_unseen_if_passed(before);
+            self.followed_new = 0;
         }
     }
 
     pub fn scroll_up(&mut self) {
-        self.log_index = self.log_index.saturating_sub(1);
+        if self.grouped {
+            self.select_group_row(self.group_cursor.saturating_sub(1));
+            return;
+        }
+        let before = self.log_index;
+        self.log_index = (0..before)
+            .rev()
+            .find(|&i| self.row_visible(&self.logs[i]))
+            .unwrap_or(before);
+        self.clear_unseen_if_passed(before);
+        self.followed_new = 0;
+        if self.log_index == 0 {
+            self.new_entries = 0;
+        }
+    }
+
+    /// Drop the unseen marker once the selection moved across it.
+    fn clear_unseen_if_passed(&mut self, before: usize) {
+        if (before < self.unseen_entries) != (self.log_index < self.unseen_entries) {
+            self.unseen_entries = 0;
+        }
+    }
+
+    /// Whether `log` contains the find term, in its message and logger or,
+    /// with `find_all_fields`, anywhere in the document.
+    pub fn find_matches(&self, log: &LogEntry) -> bool {
+        if self.find_text.is_empty() {
+            return false;
+        }
+        let term = self.find_text.to_lowercase();
+        if self.config.find_all_fields {
+            log.source.to_string().to_lowercase().contains(&term)
+        } else {
+            log.message.to_lowercase().contains(&term) || log.logger.to_lowercase().contains(&term)
+        }
+    }
+
+    /// Whether `log` is shown in the table: everything is, unless the find
+    /// term filters the page.
+    pub fn row_visible(&self, log: &LogEntry) -> bool {
+        !self.filtering_page() || self.find_matches(log)
+    }
+
+    /// Whether rows not matching the find term are hidden.
+    pub fn filtering_page(&self) -> bool {
+        self.find_filter && !self.find_text.is_empty()
+    }
+
+    /// Prompt for a term to narrow the loaded page to, without refetching.
+    pub fn start_local_filter(&mut self) {
+        self.find_text.clear();
+        self.find_filter = true;
+        self.finding = false;
+        self.focused = Pane::Find;
+    }
+
+    /// Move the selection onto a shown row after the filter term changed,
+    /// and say how many rows are left.
+    pub fn local_filter_edited(&mut self) {
+        if self.filtering_page() {
+            let shown = self.select_visible_row();
+            self.status =
+                format!("{} of {} entries match \"{}\"", shown, self.logs.len(), self.find_text);
+        }
+    }
+
+    /// Select the first shown row from the selection on, or the first one
+    /// if there is none. Returns how many rows are shown.
+    fn select_visible_row(&mut self) -> usize {
+        let visible: Vec<usize> = (0..self.logs.len())
+            .filter(|&i| self.row_visible(&self.logs[i]))
+            .collect();
+        if let Some(&index) = visible.iter().find(|&&i| i >= self.log_index).or(visible.first()) {
+            self.log_index = index;
+        }
+        visible.len()
+    }
+
+    /// Select the first row from the selection on that matches the find term.
+    pub fn start_find(&mut self) {
+        let current = self.log_index;
+        self.finding = true;
+        self.select_match(|matches| matches.iter().find(|&&i| i >= current).or(matches.first()));
+    }
+
+    /// Drop the find term and leave find mode, giving `n`/`N` back to
+    /// their usual bindings.
+    pub fn clear_find(&mut self) {
+        self.find_text.clear();
+        self.finding = false;
+        self.status = if std::mem::take(&mut self.find_filter) {
+            "Page filter cleared".to_string()
+        } else {
+            "Find cleared".to_string()
+        };
+    }
+
+    /// Select the next (or previous) row matching the find term, wrapping around.
+    pub fn find_next(&mut self, forward: bool) {
+        let current = self.log_index;
+        if forward {
+            self.select_match(|matches| matches.iter().find(|&&i| i > current).or(matches.first()));
+        } else {
+            self.select_match(|matches| {
+                matches.iter().rev().find(|&&i| i < current).or(matches.last())
+            });
+        }
+    }
+
+    fn select_match(&mut self, pick: impl Fn(&[usize]) -> Option<&usize>) {
+        let matches: Vec<usize> = (0..self.logs.len())
+            .filter(|&i| self.find_matches(&self.logs[i]))
+            .collect();
+        match pick(&matches) {
+            Some(&index) => {
+                self.log_index = index;
+                let nth = matches.iter().position(|&i| i == index).unwrap_or(0) + 1;
+                self.status = format!(
+                    "Match {} of {} for \"{}\" (n/N to step, Esc to stop)",
+                    nth,
+                    matches.len(),
+                    self.find_text
+                );
+            }
+            None => self.status = format!("No matches for \"{}\" on this page", self.find_text),
+        }
+    }
+
+    /// Select the next (or previous) entry at ERROR or worse on the page.
+    pub fn jump_to_error(&mut self, forward: bool) {
+        let is_error = |log: &LogEntry| severity_rank(&log.severity) <= severity_rank("ERROR");
+        let found = if forward {
+            (self.log_index + 1..self.logs.len()).find(|&i| is_error(&self.logs[i]))
+        } else {
+            (0..self.log_index).rev().find(|&i| is_error(&self.logs[i]))
+        };
+        match found {
+            Some(index) => self.log_index = index,
+            None => self.status = "No more errors on this page".to_string(),
+        }
+    }
+
+    /// The page's entries grouped by application, noisiest first.
+    pub fn app_groups(&self) -> Vec<AppGroup> {
+        let mut groups: Vec<AppGroup> = Vec::new();
+        for (index, log) in self.logs.iter().enumerate() {
+            let Some(group) = groups.iter_mut().find(|g| g.application == log.application) else {
+                groups.push(AppGroup {
+                    application: log.application.clone(),
+                    entries: vec![index],
+                    worst: index,
+                    latest: index,
+                });
+                continue;
+            };
+            group.entries.push(index);
+            if severity_rank(&log.severity) < severity_rank(&self.logs[group.worst].severity) {
+                group.worst = index;
+            }
+            if log.timestamp > self.logs[group.latest].timestamp {
+                group.latest = index;
+            }
+        }
+        groups.sort_by_key(|group| std::cmp::Reverse(group.entries.len()));
+        groups
+    }
+
+    /// Rows of the grouped view: each group, followed by its entries if expanded.
+    pub fn group_rows(&self) -> Vec<GroupRow> {
+        let mut rows = Vec::new();
+        for group in self.app_groups() {
+            let entries = if self.expanded_groups.contains(&group.application) {
+                group.entries.clone()
+            } else {
+                Vec::new()
+            };
+            rows.push(GroupRow::Group(group));
+            rows.extend(entries.into_iter().map(GroupRow::Entry));
+        }
+        rows
+    }
+
+    /// Switch between the flat list and the grouped view.
+    pub fn toggle_grouped(&mut self) {
+        self.grouped = !self.grouped;
+        self.select_group_row(0);
+    }
+
+    /// Whether the grouped view's cursor is on a group rather than an entry.
+    pub fn on_group_row(&self) -> bool {
+        matches!(self.group_rows().get(self.group_cursor), Some(GroupRow::Group(_)))
+    }
+
+    /// Expand or collapse the group under the cursor.
+    pub fn toggle_selected_group(&mut self) {
+        if let Some(GroupRow::Group(group)) = self.group_rows().into_iter().nth(self.group_cursor)
+            && !self.expanded_groups.remove(&group.application)
+        {
+            self.expanded_groups.insert(group.application);
+        }
+    }
+
+    /// Move the grouped view's cursor, selecting the entry under it or, on a
+    /// group, that group's most severe entry.
+    fn select_group_row(&mut self, row: usize) {
+        self.group_cursor = row;
+        match self.group_rows().into_iter().nth(row) {
+            Some(GroupRow::Group(group)) => self.log_index = group.worst,
+            Some(GroupRow::Entry(index)) => self.log_index = index,
+            None => {}
+        }
+    }
+
+    /// Whether the stacktrace of row `index` is shown inline.
+    pub fn stacktrace_expanded(&self, index: usize) -> bool {
+        self.stacktraces_expanded != self.toggled_stacktraces.contains(&index)
+    }
+
+    /// Expand or collapse the selected row's stacktrace.
+    pub fn toggle_stacktrace(&mut self) {
+        let Some(log) = self.logs.get(self.log_index) else {
+            return;
+        };
+        if log.stacktrace.is_empty() {
+            self.status = "No stacktrace on this entry".to_string();
+        } else if !self.toggled_stacktraces.remove(&self.log_index) {
+            self.toggled_stacktraces.insert(self.log_index);
+        }
+    }
+
+    /// Expand or collapse the stacktraces of all rows.
+    pub fn toggle_all_stacktraces(&mut self) {
+        self.stacktraces_expanded = !self.stacktraces_expanded;
+        self.toggled_stacktraces.clear();
+    }
+
+    /// A log entry as a `[timestamp] LEVEL [logger] message` line, followed by
+    /// its stacktrace if `export_stacktraces` is enabled.
+    pub fn format_log_line(&self, log: &LogEntry) -> String {
+        export::text_line(log, self.config.export_stacktraces, self.config.export_max_length)
+    }
+
+    /// Prompt for a path to save the selected entry to.
+    pub fn start_save_entry(&mut self, format: ExportFormat) {
+        self.save_format = format;
+        self.save_page = false;
+        self.save_path = format!("log_entry.{}", format.extension());
+        self.focused = Pane::SaveEntry;
+    }
+
+    /// Prompt for a path to save the loaded page to as JSON, defaulting to
+    /// the temp dir. Tab in the prompt switches to CSV or text.
+    pub fn start_save_page(&mut self) {
+        if self.logs.is_empty() {
+            self.status = "No entries to export".to_string();
+            return;
+        }
+        self.save_format = ExportFormat::Json;
+        self.save_page = true;
+        self.save_path = std::env::temp_dir()
+            .join(format!("log_explorer_page_{}.json", self.page))
+            .display()
+            .to_string();
+        self.focused = Pane::SaveEntry;
+    }
+
+    /// Switch the save prompt to the next format, updating the extension of
+    /// `save_path` if it still has the old one.
+    pub fn cycle_save_format(&mut self) {
+        let old = format!(".{}", self.save_format.extension());
+        self.save_format = self.save_format.next();
+        if let Some(stem) = self.save_path.strip_suffix(&old) {
+            self.save_path = format!("{}.{}", stem, self.save_format.extension());
+        }
+    }
+
+    /// Write the selected entry, or the page if `save_page` is set, to `save_path`.
+    pub fn save_entry(&mut self) {
+        let (stacktraces, max_length) =
+            (self.config.export_stacktraces, self.config.export_max_length);
+        let contents = if self.save_page {
+            export::format_entries(&self.logs, self.save_format, stacktraces, max_length)
+        } else if let Some(log) = self.logs.get(self.log_index) {
+            export::format_entry(log, self.save_format, stacktraces, max_length)
+        } else {
+            return;
+        };
+        let what = if self.save_page {
+            format!("{} entries", self.logs.len())
+        } else {
+            "entry".to_string()
+        };
+        self.status = match export::write_file(&self.save_path, &contents) {
+            Ok(path) => format!("Saved {} to {}", what, path.display()),
+            Err(e) => format!("Failed to save {}: {:#}", what, e),
+        };
+    }
+
+    pub fn open_detail(&mut self) {
+        if !self.logs.is_empty() {
+            self.detail_scroll = 0;
+            self.detail_selection = None;
+            self.focused = Pane::Detail;
+        }
+    }
+
+    /// Start selecting part of the message, from its first character.
+    pub fn start_detail_selection(&mut self) {
+        if self.logs.get(self.log_index).is_some_and(|log| !log.message.is_empty()) {
+            self.detail_scroll = 0;
+            self.detail_selection = Some(TextSelection { anchor: 0, cursor: 0 });
+        }
+    }
+
+    /// Move the selection cursor one character, or to the previous or next
+    /// word start if `word` is set, extending the selection.
+    pub fn move_selection_cursor(&mut self, forward: bool, word: bool) {
+        let Some(log) = self.logs.get(self.log_index) else {
+            return;
+        };
+        let Some(selection) = &mut self.detail_selection else {
+            return;
+        };
+        let chars: Vec<char> = log.message.chars().collect();
+        let last = chars.len().saturating_sub(1);
+        let mut at = selection.cursor;
+        if !word {
+            at = if forward { (at + 1).min(last) } else { at.saturating_sub(1) };
+        } else if forward {
+            while at < last && !chars[at].is_whitespace() {
+                at += 1;
+            }
+            while at < last && chars[at].is_whitespace() {
+                at += 1;
+            }
+        } else {
+            at = at.saturating_sub(1);
+            while at > 0 && chars[at].is_whitespace() {
+                at -= 1;
+            }
+            while at > 0 && !chars[at - 1].is_whitespace() {
+                at -= 1;
+            }
+        }
+        selection.cursor = at;
+    }
+
+    /// The selected part of the message.
+    pub fn selected_text(&self) -> Option<String> {
+        let selection = self.detail_selection?;
+        let log = self.logs.get(self.log_index)?;
+        let range = selection.range();
+        Some(
+            log.message
+                .chars()
+                .skip(*range.start())
+                .take(range.end() - range.start() + 1)
+                .collect(),
+        )
+    }
+
+    /// The selected entry's fields with a value, as dotted paths and values.
+    pub fn pivot_fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        if let Some(log) = self.logs.get(self.log_index) {
+            scalar_fields(String::new(), &log.source, &mut fields);
+        }
+        fields
+    }
+
+    /// Replace the search with one for entries having the field under the
+    /// pivot cursor set to the same value, and refetch. Later searches
+    /// narrow the pivot down until the filters are reset.
+    pub fn pivot_search(&mut self) {
+        let Some((field, value)) = self.pivot_fields().into_iter().nth(self.pivot_cursor) else {
+            return;
+        };
+        self.pivot = Some((field, value));
+        self.search_text.clear();
+        self.search_mode_filter.select_first();
+        self.focused = Pane::Logs;
+        self.fetch_logs();
+    }
+
+    /// Scroll the detail view down, stopping at the last of `line_count` lines.
+    pub fn scroll_detail_down(&mut self, line_count: usize) {
+        if (self.detail_scroll as usize) + 1 < line_count {
+            self.detail_scroll += 1;
+        }
+    }
+
+    pub fn scroll_detail_up(&mut self) {
+        self.detail_scroll = self.detail_scroll.saturating_sub(1);
+    }
+
+    /// Report that an action is unavailable in read-only mode.
+    pub fn refuse_read_only(&mut self) {
+        self.status = "Not available in read-only mode".to_string();
+    }
+
+    /// Switch to the next detail rendering mode and remember it as the default.
+    pub fn cycle_detail_mode(&mut self) {
+        self.detail_hex = false;
+        self.detail_mode = self.detail_mode.next();
+        if self.config.read_only {
+            self.status = format!("Detail view: {}", self.detail_mode.label());
+            return;
+        }
+        self.config.detail_mode = self.detail_mode;
+        self.status = match config::save_config(&self.config) {
+            Ok(_) => format!("Detail view: {}", self.detail_mode.label()),
+            Err(e) => format!("Failed to save config: {}", e),
+        };
+    }
+}
+
+/// Count entries per severity, ordered from most to least severe.
+fn severity_breakdown(logs: &[LogEntry]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for log in logs {
+        match counts.iter_mut().find(|(severity, _)| *severity == log.severity) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((log.severity.clone(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| {
+        severity_rank(&a.0)
+            .cmp(&severity_rank(&b.0))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    counts
+}
+
+/// Collect the non-empty scalar values under `value` with their dotted paths.
+/// Array elements share their array's path, as in queries.
+fn scalar_fields(path: String, value: &serde_json::Value, out: &mut Vec<(String, String)>) {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                scalar_fields(path, value, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                scalar_fields(path.clone(), item, out);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) if s.trim().is_empty() => {}
+        Value::String(s) => out.push((path, s.clone())),
+        other => out.push((path, other.to_string())),
+    }
+}
+
+/// The number-unit pairs of a duration like `90m` or `3d12h`, e.g. `["3d",
+/// "12h"]`. `None` unless the whole text is such pairs, with units s, m, h,
+/// d or w.
+fn duration_parts(text: &str) -> Option<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            's' | 'm' | 'h' | 'd' | 'w' if !number.is_empty() => {
+                parts.push(format!("{}{}", std::mem::take(&mut number), c));
+            }
+            _ => return None,
+        }
+    }
+    (number.is_empty() && !parts.is_empty()).then_some(parts)
+}
+
+/// The window of a typed `from..to` range, e.g.
+/// `2024-03-01T10:00..2024-03-01T11:30`, or why it isn't valid. `None` if
+/// `text` isn't a range at all.
+fn absolute_range(text: &str) -> Option<Result<TimeWindow, String>> {
+    let (from, to) = text.split_once("..")?;
+    Some(parse_time(from).and_then(|from| {
+        let to = parse_time(to)?;
+        if from >= to {
+            return Err("The range must end after it starts".to_string());
+        }
+        Ok((from, to))
+    }))
+}
+
+/// A typed point in time, down to the minute or the day; UTC without an offset.
+fn parse_time(text: &str) -> Result<DateTime<Utc>, String> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|time| time.and_utc())
+        .ok_or_else(|| format!("Invalid time \"{}\" — use e.g. 2024-03-01T10:00", text))
+}
+
+/// Seconds in a duration's number-unit `parts`.
+fn duration_secs(parts: &[String]) -> i64 {
+    parts
+        .iter()
+        .map(|part| {
+            let (number, unit) = part.split_at(part.len() - 1);
+            let unit = match unit {
+                "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86_400,
+                _ => 604_800,
+            };
+            number.parse::<i64>().unwrap_or(0) * unit
+        })
+        .sum()
+}
+
+/// `query` with only what decides the histogram: its filters, not the page.
+fn histogram_query(query: &LogQuery) -> LogQuery {
+    LogQuery { sort: SortOrder::Time, size: 0, from: 0, ..query.clone() }
+}
+
+/// Date math moving by each of the duration `parts` in the `sign` direction,
+/// e.g. `-3d-12h`.
+fn date_math(parts: &[String], sign: char) -> String {
+    parts.iter().map(|part| format!("{}{}", sign, part)).collect()
+}
+
+/// Position of a severity from most to least severe; unknown ones sort last.
+pub fn severity_rank(severity: &str) -> usize {
+    ["FATAL", "ERROR", "WARN", "INFO", "DEBUG", "TRACE"]
+        .iter()
+        .position(|known| known.eq_ignore_ascii_case(severity))
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Auth, FieldMapping};
+    use serde_json::json;
+
+    fn app_with_messages(messages: &[&str]) -> App {
+        let mut app = App::new(AppConfig::new("http://localhost:9200".to_string(), Auth::None));
+        app.logs = messages
+            .iter()
+            .map(|message| {
+                let source = json!({
+                    "@timestamp": "2024-03-01T10:00:00Z",
+                    "severity": "INFO",
+                    "message": message,
+                });
+                LogEntry::from_source(source, &FieldMapping::default()).unwrap()
+            })
+            .collect();
+        app
+    }
+
+    #[test]
+    fn find_mode_starts_on_find_and_ends_on_clear() {
+        let mut app = app_with_messages(&["ok", "timeout", "ok", "timeout"]);
+        assert!(!app.finding);
+
+        app.find_text = "timeout".to_string();
+        app.start_find();
+        assert!(app.finding);
+        assert_eq!(app.log_index, 1);
+        app.find_next(true);
+        assert_eq!(app.log_index, 3);
+        app.find_next(false);
+        assert_eq!(app.log_index, 1);
+
+        app.clear_find();
+        assert!(!app.finding);
+        assert!(app.find_text.is_empty());
+    }
+
+    #[test]
+    fn page_filter_is_not_find_mode() {
+        let mut app = app_with_messages(&["ok", "timeout"]);
+        app.find_text = "timeout".to_string();
+        app.start_find();
+        app.start_local_filter();
+        assert!(!app.finding);
+        assert!(app.find_filter);
+    }
+
+    #[test]
+    fn distinct_counts_arrive_over_the_channel() {
+        let mut app = app_with_messages(&[]);
+        let outcome = DistinctOutcome { field: "userId".to_string(), result: Ok(42) };
+        app.distinct_tx.send(outcome).unwrap();
+        app.poll_fetches();
+        assert_eq!(app.status, "42 distinct userId values in the current query");
+    }
+
+    fn outcome(app: &App, seq: u64, messages: &[&str]) -> FetchOutcome {
+        let logs = app_with_messages(messages).logs;
+        let total = logs.len() as u64;
+        FetchOutcome {
+            seq,
+            page: 1,
+            label: "ALL (production)".to_string(),
+            query: app.log_query("production".to_string(), 1),
+            result: Ok(LogResult { logs, total, malformed: 0, note: None }),
+        }
+    }
+
+    #[tokio::test]
+    async fn newer_fetch_supersedes_a_pending_one() {
+        let mut app = app_with_messages(&[]);
+        app.profile_filter.set_items(vec!["production".to_string()]);
+        app.profile_filter.select_value("production");
+
+        // Nothing is awaited, so neither request gets to run
+        app.fetch_page(1);
+        let first = app.fetch_seq;
+        app.fetch_page(1);
+        let second = app.fetch_seq;
+        assert!(second > first);
+        assert!(app.pending);
+
+        app.fetch_tx.send(outcome(&app, first, &["stale"])).unwrap();
+        app.poll_fetches();
+        assert!(app.pending);
+        assert!(app.logs.is_empty());
+
+        app.fetch_tx.send(outcome(&app, second, &["fresh"])).unwrap();
+        app.fetch_tx.send(outcome(&app, first, &["stale"])).unwrap();
+        app.poll_fetches();
+        assert!(!app.pending);
+        let messages: Vec<&str> = app.logs.iter().map(|log| log.message.as_str()).collect();
+        assert_eq!(messages, ["fresh"]);
+    }
+
+    #[tokio::test]
+    async fn reset_filters_restores_the_defaults() {
+        let mut app = app_with_messages(&[]);
+        app.profile_filter.set_items(vec!["staging".to_string(), "production".to_string()]);
+        app.app_filter.set_items(vec![ALL.to_string(), "billing".to_string(), "checkout".to_string()]);
+        app.severity_filter.set_items(vec![ALL.to_string(), "ERROR".to_string()]);
+        app.time_filter.set_items(TIME_RANGES.iter().map(|s| s.to_string()).collect());
+        app.limit_filter.set_items(vec![DEFAULT_LIMIT.to_string(), "200".to_string()]);
+
+        app.profile_filter.select_value("staging");
+        app.app_filter.check_value("billing");
+        app.app_filter.check_value("checkout");
+        app.severity_filter.select_value("ERROR");
+        app.severity_exclude = true;
+        app.time_filter.select_value("24h");
+        app.limit_filter.select_value("200");
+        app.search_text = "timeout".to_string();
+        app.exclude_text = "healthcheck".to_string();
+        app.raw_query = Some(json!({ "query": { "match_all": {} } }));
+
+        app.reset_filters();
+        assert_eq!(app.selected_env(), Some(DEFAULT_PROFILE));
+        assert!(app.selected_apps().is_empty());
+        assert_eq!(app.severity_filter.selected_value(), Some(ALL));
+        assert!(!app.severity_exclude);
+        assert_eq!(app.time_filter.selected_value(), Some(DEFAULT_TIME_RANGE));
+        assert_eq!(app.limit_filter.selected_value(), Some(DEFAULT_LIMIT));
+        assert!(app.search_text.is_empty());
+        assert!(app.exclude_text.is_empty());
+        assert!(app.raw_query.is_none());
+        assert!(app.pending);
+    }
+
+    fn app_with_filters() -> App {
+        let mut app = app_with_messages(&[]);
+        app.profile_filter.set_items(vec!["production".to_string(), "staging".to_string()]);
+        app.profile_filter.select_value(DEFAULT_PROFILE);
+        app.app_filter.set_items(vec![ALL.to_string(), "billing".to_string(), "checkout".to_string()]);
+        app.severity_filter.set_items(vec![ALL.to_string(), "ERROR".to_string(), "WARN".to_string()]);
+        app.time_filter.set_items(TIME_RANGES.iter().map(|s| s.to_string()).collect());
+        app.time_filter.select_value(DEFAULT_TIME_RANGE);
+        app.limit_filter.set_items(vec![DEFAULT_LIMIT.to_string(), "200".to_string()]);
+        app
+    }
+
+    #[test]
+    fn last_filters_are_selected_again() {
+        let mut app = app_with_filters();
+        app.profile_filter.select_value("staging");
+        app.app_filter.check_value("billing");
+        app.app_filter.check_value("checkout");
+        app.severity_filter.select_value("ERROR");
+        app.time_filter.select_value("1h");
+        app.limit_filter.select_value("200");
+        app.search_text = "timeout".to_string();
+        let last = app.last_filters().unwrap();
+        assert_eq!(last.search, None);
+
+        let mut next = app_with_filters();
+        next.config.last_filters = last;
+        next.apply_last_filters();
+        assert_eq!(next.selected_env(), Some("staging"));
+        assert_eq!(next.selected_apps(), ["billing", "checkout"]);
+        assert_eq!(next.severity_filter.selected_value(), Some("ERROR"));
+        assert_eq!(next.time_filter.selected_value(), Some("1h"));
+        assert_eq!(next.limit_filter.selected_value(), Some("200"));
+    }
+
+    #[test]
+    fn stale_last_filters_keep_the_defaults() {
+        let mut app = app_with_filters();
+        app.config.last_filters = FilterState {
+            profile: Some("qa".to_string()),
+            application: Some(ALL.to_string()),
+            applications: vec!["billing".to_string(), "retired-service".to_string()],
+            severities: vec!["NOTICE".to_string()],
+            severity_exclude: true,
+            limit: Some("5000".to_string()),
+            time_range: Some("1h".to_string()),
+            ..FilterState::default()
+        };
+        app.apply_last_filters();
+        assert_eq!(app.selected_env(), Some(DEFAULT_PROFILE));
+        assert!(app.selected_apps().is_empty());
+        assert!(app.severity_filter.checked_values().is_empty());
+        assert!(!app.severity_exclude);
+        assert_eq!(app.limit_filter.selected_value(), Some(DEFAULT_LIMIT));
+        assert_eq!(app.time_filter.selected_value(), Some("1h"));
+    }
+
+    #[test]
+    fn nothing_is_remembered_before_the_filters_load() {
+        let app = app_with_messages(&[]);
+        assert!(app.last_filters().is_none());
+    }
+
+    #[test]
+    fn saved_defaults_keep_the_view_toggles() {
+        let mut app = app_with_filters();
+        app.dense = true;
+        app.two_line = true;
+        app.relative_time = true;
+        app.show_trace_id = true;
+        app.store_defaults();
+
+        let saved = toml::to_string(&app.config).unwrap();
+        let restarted = App::new(toml::from_str(&saved).unwrap());
+        assert!(restarted.dense);
+        assert!(restarted.two_line);
+        assert!(restarted.relative_time);
+        assert!(restarted.show_trace_id);
+    }
+
+    #[tokio::test]
+    async fn pivot_on_an_unmapped_path_is_a_field_clause() {
+        let mut app = app_with_filters();
+        let source = json!({
+            "@timestamp": "2024-03-01T10:00:00Z",
+            "message": "order placed",
+            "context": { "userId": 42 },
+        });
+        app.logs = vec![LogEntry::from_source(source, &FieldMapping::default()).unwrap()];
+        app.search_text = "order".to_string();
+        let fields = app.pivot_fields();
+        app.pivot_cursor = fields.iter().position(|(field, _)| field == "context.userId").unwrap();
+
+        app.pivot_search();
+        assert_eq!(app.pivot, Some(("context.userId".to_string(), "42".to_string())));
+        assert!(app.search_text.is_empty());
+        let body = app.query_body().unwrap();
+        let must = &body["query"]["bool"]["must"];
+        let clauses = must.as_array().unwrap();
+        assert!(clauses.contains(&json!({"match_phrase": {"context.userId": "42"}})));
+        assert!(!body.to_string().contains("userId:"));
     }
 }
@@ -1,25 +1,557 @@
+use crate::opensearch::SortOrder;
 use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, TableLike};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub endpoint_url: String,
-    #[serde(default = "default_region")]
+    /// How requests to the cluster are authenticated.
+    #[serde(default)]
+    pub auth: Auth,
+    /// Region of configs from before `auth`; moved into `Auth::Aws` on load.
+    #[serde(default, skip_serializing)]
     pub aws_region: String,
+    /// Rendering mode the detail view opens in.
+    #[serde(default)]
+    pub detail_mode: DetailMode,
+    /// Re-run the search as you type instead of only on Enter.
+    #[serde(default)]
+    pub live_search: bool,
+    /// Document field holding the severity; may be a dotted path like `log.level`.
+    #[serde(default = "default_severity_field")]
+    pub severity_field: String,
+    /// Document fields the other parts of an entry are read from.
+    #[serde(default)]
+    pub fields: FieldMapping,
+    /// Display names for raw severity values, e.g. `{"3": "ERROR"}` for syslog levels.
+    #[serde(default)]
+    pub severity_names: BTreeMap<String, String>,
+    /// Applications pinned to the top of the application dropdown.
+    #[serde(default)]
+    pub favorite_apps: Vec<String>,
+    /// How many fetched pages to keep for instant paging back (0 disables).
+    #[serde(default = "default_page_cache_size")]
+    pub page_cache_size: usize,
+    /// How long a cached page stays valid, in seconds.
+    #[serde(default = "default_page_cache_ttl_secs")]
+    pub page_cache_ttl_secs: u64,
+    /// PEM file with an extra root CA to trust, for clusters behind a private CA.
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// OpenSearch Dashboards base URL, for Discover links.
+    #[serde(default)]
+    pub dashboards_url: Option<String>,
+    /// Id of the Dashboards index pattern Discover links open in.
+    #[serde(default)]
+    pub dashboards_index_pattern: Option<String>,
+    /// Link to a trace in the tracing UI, with `{traceId}` standing for the
+    /// entry's trace ID, e.g. `https://jaeger.example.com/trace/{traceId}`.
+    #[serde(default)]
+    pub trace_url_template: Option<String>,
+    /// Append stacktraces when copying or exporting formatted log lines.
+    #[serde(default = "default_true")]
+    pub export_stacktraces: bool,
+    /// Characters of a message or stacktrace kept when copying or exporting;
+    /// 0 keeps everything.
+    #[serde(default = "default_export_max_length")]
+    pub export_max_length: usize,
+    /// Show the log volume over the time range above the table.
+    #[serde(default = "default_true")]
+    pub show_histogram: bool,
+    /// Seconds between refetches of the first page while live tail is on.
+    #[serde(default = "default_live_tail_interval_secs")]
+    pub live_tail_interval_secs: u64,
+    /// Pause live tail after this many minutes without a key press; 0 never pauses.
+    #[serde(default)]
+    pub live_tail_idle_mins: u64,
+    /// How application names are shortened in the filter bar and dropdown.
+    #[serde(default)]
+    pub app_name_display: NameShortening,
+    /// Friendly names shown instead of real application and logger names,
+    /// which are still used in queries.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    /// Filter selections applied at startup and on reset.
+    #[serde(default)]
+    pub defaults: FilterState,
+    /// Profile, applications, severities, time range and limit in use when
+    /// the app last exited, selected again over `defaults` on startup.
+    #[serde(default)]
+    pub last_filters: FilterState,
+    /// Always show the relevance score column, not just when sorting by it.
+    #[serde(default)]
+    pub show_score: bool,
+    /// Start with the trace ID column shown.
+    #[serde(default)]
+    pub show_trace_id: bool,
+    /// Time range to switch to when a severity is selected, e.g. `{"ERROR": "24h"}`.
+    #[serde(default)]
+    pub severity_time_ranges: BTreeMap<String, String>,
+    /// Extra logs table columns read from the raw document.
+    #[serde(default)]
+    pub columns: Vec<CustomColumn>,
+    /// Headers of the logs table columns to hide, first to last, when the
+    /// terminal gets too narrow. Unlisted extra and trace ID columns go first;
+    /// Timestamp, Level and Message always stay.
+    #[serde(default = "default_column_hide_order")]
+    pub column_hide_order: Vec<String>,
+    /// Find within the page searches every field instead of message and logger.
+    #[serde(default)]
+    pub find_all_fields: bool,
+    /// What Enter does on the logs pane.
+    #[serde(default)]
+    pub enter_action: EnterAction,
+    /// Index names or patterns to search.
+    #[serde(default = "default_indices")]
+    pub indices: Vec<String>,
+    /// Start with timestamps shown as "3m ago" rather than the time of day.
+    #[serde(default)]
+    pub relative_timestamps: bool,
+    /// Start with the logs table in dense mode.
+    #[serde(default)]
+    pub dense_rows: bool,
+    /// Start with messages wrapped onto two lines.
+    #[serde(default)]
+    pub two_line_rows: bool,
+    /// Timezone timestamps are shown in: `"utc"`, `"local"` or a name such
+    /// as `"Europe/Berlin"`.
+    #[serde(default)]
+    pub display_timezone: DisplayTimezone,
+    /// Stop waiting for `$EDITOR` after this many minutes and return to the
+    /// logs; 0 waits until it exits.
+    #[serde(default)]
+    pub editor_timeout_mins: u64,
+    /// Entries excluded from every search.
+    #[serde(default)]
+    pub noise: Vec<NoiseFilter>,
+    /// Disable everything that opens other programs, writes files or uses
+    /// the clipboard, e.g. while screen sharing.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Reopen the last session's filters, page and selection on startup.
+    #[serde(default)]
+    pub restore_session: bool,
+    /// Sessions older than this many minutes start fresh instead.
+    #[serde(default = "default_session_max_age_mins")]
+    pub session_max_age_mins: u64,
 }
 
-fn default_region() -> String {
-    "eu-central-1".to_string()
+impl AppConfig {
+    /// A config for the given cluster with every other option at its default.
+    pub fn new(endpoint_url: String, auth: Auth) -> Self {
+        Self {
+            endpoint_url,
+            auth,
+            aws_region: String::new(),
+            detail_mode: DetailMode::default(),
+            live_search: false,
+            severity_field: default_severity_field(),
+            fields: FieldMapping::default(),
+            severity_names: BTreeMap::new(),
+            favorite_apps: Vec::new(),
+            page_cache_size: default_page_cache_size(),
+            page_cache_ttl_secs: default_page_cache_ttl_secs(),
+            ca_cert_path: None,
+            dashboards_url: None,
+            dashboards_index_pattern: None,
+            trace_url_template: None,
+            export_stacktraces: true,
+            show_histogram: true,
+            export_max_length: default_export_max_length(),
+            live_tail_interval_secs: default_live_tail_interval_secs(),
+            live_tail_idle_mins: 0,
+            app_name_display: NameShortening::default(),
+            aliases: BTreeMap::new(),
+            defaults: FilterState::default(),
+            last_filters: FilterState::default(),
+            show_score: false,
+            show_trace_id: false,
+            severity_time_ranges: BTreeMap::new(),
+            columns: Vec::new(),
+            column_hide_order: default_column_hide_order(),
+            find_all_fields: false,
+            enter_action: EnterAction::default(),
+            indices: default_indices(),
+            relative_timestamps: false,
+            dense_rows: false,
+            two_line_rows: false,
+            display_timezone: DisplayTimezone::default(),
+            editor_timeout_mins: 0,
+            noise: Vec::new(),
+            read_only: false,
+            restore_session: false,
+            session_max_age_mins: default_session_max_age_mins(),
+        }
+    }
+
+    /// Display name for an application: its alias, or the shortened name.
+    pub fn app_display<'a>(&'a self, name: &'a str) -> Cow<'a, str> {
+        match self.aliases.get(name) {
+            Some(alias) => Cow::Borrowed(alias),
+            None => self.app_name_display.apply(name),
+        }
+    }
+
+    /// Display name for a logger: its alias, or the last segment of its name.
+    pub fn logger_display<'a>(&'a self, name: &'a str) -> &'a str {
+        match self.aliases.get(name) {
+            Some(alias) => alias,
+            None => name.rsplit('.').next().unwrap_or(name),
+        }
+    }
+
+    /// Display name for a raw severity value.
+    pub fn severity_label(&self, raw: &str) -> String {
+        self.severity_names
+            .get(raw)
+            .cloned()
+            .unwrap_or_else(|| raw.to_string())
+    }
+
+    /// Raw severity value to query for a display name.
+    pub fn severity_raw<'a>(&'a self, label: &'a str) -> &'a str {
+        self.severity_names
+            .iter()
+            .find(|(_, name)| *name == label)
+            .map(|(raw, _)| raw.as_str())
+            .unwrap_or(label)
+    }
 }
 
-pub fn config_path() -> PathBuf {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnterAction {
+    #[default]
+    ContextMenu,
+    DetailView,
+    Copy,
+}
+
+/// How requests to the cluster are authenticated.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    /// AWS SigV4 signing with credentials from ~/.aws/credentials.
+    Aws {
+        /// Region requests are signed for; empty uses AWS_REGION or the profile's.
+        #[serde(default)]
+        region: String,
+    },
+    /// HTTP basic auth, for self-hosted clusters.
+    Basic { username: String, password: String },
+    /// An OpenSearch API key, sent as `Authorization: ApiKey`.
+    #[serde(rename = "api-key")]
+    ApiKey { id: String, key: String },
+    /// No authentication.
+    None,
+}
+
+/// Leaves out passwords and keys, so they don't end up in logs.
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Auth::Aws { region } => f.debug_struct("Aws").field("region", region).finish(),
+            Auth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Auth::ApiKey { id, .. } => f.debug_struct("ApiKey").field("id", id).finish_non_exhaustive(),
+            Auth::None => f.write_str("None"),
+        }
+    }
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Auth::Aws { region: String::new() }
+    }
+}
+
+/// Document fields holding each part of a log entry, for schemas that name
+/// them differently. Any may be a dotted path like `service.name`; the
+/// severity is read from `severity_field`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldMapping {
+    pub timestamp: String,
+    pub message: String,
+    pub application: String,
+    pub logger: String,
+    pub thread: String,
+    /// Field holding the environment the profile filter selects.
+    pub profile: String,
+    pub stacktrace: String,
+    pub trace_id: String,
+}
+
+impl Default for FieldMapping {
+    fn default() -> Self {
+        Self {
+            timestamp: "@timestamp".to_string(),
+            message: "message".to_string(),
+            application: "application".to_string(),
+            logger: "logger".to_string(),
+            thread: "thread".to_string(),
+            profile: "profiles".to_string(),
+            stacktrace: "stacktrace".to_string(),
+            trace_id: "traceId".to_string(),
+        }
+    }
+}
+
+/// A pattern of known-benign entries to hide. An entry matches if it matches
+/// every pattern that is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoiseFilter {
+    /// Exact logger name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+    /// Substring of the message; `*` and `?` are wildcards.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A logs table column showing a document field, e.g. `context.userId`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomColumn {
+    pub header: String,
+    /// Dotted path into `_source`.
+    pub path: String,
+    #[serde(default = "default_column_width")]
+    pub width: u16,
+}
+
+/// Where the user left off, saved on exit for `restore_session`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub filters: FilterState,
+    pub page: u64,
+    pub log_index: usize,
+    #[serde(default)]
+    pub grouped: bool,
+    #[serde(default)]
+    pub find_text: String,
+    /// Unix time the session was saved at.
+    pub saved_at: i64,
+}
+
+/// A snapshot of the interactive filter selections. Unset fields leave the
+/// corresponding filter alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub application: Option<String>,
+    /// Applications picked together, shown instead of `application`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applications: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// Severities picked together, shown instead of `severity`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub severities: Vec<String>,
+    /// Leave the selected severities out instead of showing only them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub severity_exclude: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_range: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_fields: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortOrder>,
+    /// Field path and value picked from an entry to narrow the search to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pivot: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetailMode {
+    Text,
+    #[default]
+    Json,
+    Logfmt,
+}
+
+impl DetailMode {
+    pub fn next(self) -> Self {
+        match self {
+            DetailMode::Text => DetailMode::Json,
+            DetailMode::Json => DetailMode::Logfmt,
+            DetailMode::Logfmt => DetailMode::Text,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DetailMode::Text => "Text",
+            DetailMode::Json => "JSON",
+            DetailMode::Logfmt => "logfmt",
+        }
+    }
+}
+
+/// Timezone timestamps are converted to before they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DisplayTimezone {
+    #[default]
+    Utc,
+    /// The system's timezone.
+    Local,
+    Named(Tz),
+}
+
+impl DisplayTimezone {
+    pub fn convert(self, time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        match self {
+            DisplayTimezone::Utc => time.with_timezone(&Utc).fixed_offset(),
+            DisplayTimezone::Local => time.with_timezone(&Local).fixed_offset(),
+            DisplayTimezone::Named(tz) => time.with_timezone(&tz).fixed_offset(),
+        }
+    }
+
+    /// Short name for the status bar, e.g. `UTC` or `Europe/Berlin`.
+    pub fn label(self) -> String {
+        match self {
+            DisplayTimezone::Utc => "UTC".to_string(),
+            DisplayTimezone::Local => format!("local {}", Local::now().format("%:z")),
+            DisplayTimezone::Named(tz) => tz.name().to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for DisplayTimezone {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "utc" => Ok(DisplayTimezone::Utc),
+            "local" => Ok(DisplayTimezone::Local),
+            _ => name
+                .parse()
+                .map(DisplayTimezone::Named)
+                .map_err(|_| format!("unknown timezone `{}`", name)),
+        }
+    }
+}
+
+impl From<DisplayTimezone> for String {
+    fn from(zone: DisplayTimezone) -> String {
+        match zone {
+            DisplayTimezone::Utc => "utc".to_string(),
+            DisplayTimezone::Local => "local".to_string(),
+            DisplayTimezone::Named(tz) => tz.name().to_string(),
+        }
+    }
+}
+
+/// Display form of a dotted name such as `com.acme.billing.invoice-service`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NameShortening {
+    /// `com.acme.billing.invoice-service`
+    #[default]
+    Full,
+    /// `invoice-service`
+    LastSegment,
+    /// `c.a.b.invoice-service`
+    FirstLetters,
+}
+
+impl NameShortening {
+    pub fn apply(self, name: &str) -> Cow<'_, str> {
+        let Some((prefix, last)) = name.rsplit_once('.') else {
+            return Cow::Borrowed(name);
+        };
+        match self {
+            NameShortening::Full => Cow::Borrowed(name),
+            NameShortening::LastSegment => Cow::Borrowed(last),
+            NameShortening::FirstLetters => {
+                let mut short: String = prefix
+                    .split('.')
+                    .filter_map(|segment| segment.chars().next())
+                    .flat_map(|c| [c, '.'])
+                    .collect();
+                short.push_str(last);
+                Cow::Owned(short)
+            }
+        }
+    }
+}
+
+fn default_column_width() -> u16 {
+    20
+}
+
+fn default_column_hide_order() -> Vec<String> {
+    ["Score", "Logger", "ST"].map(String::from).to_vec()
+}
+
+fn default_indices() -> Vec<String> {
+    vec!["logs-*".to_string()]
+}
+
+fn default_severity_field() -> String {
+    "severity".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_page_cache_size() -> usize {
+    10
+}
+
+fn default_page_cache_ttl_secs() -> u64 {
+    120
+}
+
+fn default_live_tail_interval_secs() -> u64 {
+    5
+}
+
+fn default_export_max_length() -> usize {
+    10_000
+}
+
+fn default_session_max_age_mins() -> u64 {
+    8 * 60
+}
+
+fn config_dir() -> PathBuf {
     let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home)
-        .join(".config")
-        .join("log_explorer")
-        .join("config.json")
+    PathBuf::from(home).join(".config").join("log_explorer")
+}
+
+/// The config file: `config.toml` if it exists, otherwise `config.json`.
+pub fn config_path() -> PathBuf {
+    let dir = config_dir();
+    let toml = dir.join("config.toml");
+    if toml.exists() { toml } else { dir.join("config.json") }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
 }
 
 pub fn load_config() -> Result<Option<AppConfig>> {
@@ -28,16 +560,200 @@ pub fn load_config() -> Result<Option<AppConfig>> {
         return Ok(None);
     }
     let contents = fs::read_to_string(&path)?;
-    let config: AppConfig = serde_json::from_str(&contents)?;
+    let mut config: AppConfig = if is_toml(&path) {
+        toml::from_str(&contents)?
+    } else {
+        serde_json::from_str(&contents)?
+    };
+    config.endpoint_url = normalize_endpoint(&config.endpoint_url)?;
+    if let Auth::Aws { region } = &mut config.auth
+        && region.is_empty()
+    {
+        *region = std::mem::take(&mut config.aws_region);
+    }
     Ok(Some(config))
 }
 
+/// Check that an endpoint URL has a scheme and host, assuming `https://`
+/// when no scheme is given.
+pub fn normalize_endpoint(input: &str) -> Result<String> {
+    let input = input.trim();
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+    let mut url = url::Url::parse(&with_scheme)
+        .map_err(|e| anyhow::anyhow!("Invalid endpoint URL {}: {}", input, e))?;
+    if url.scheme() != "https" && url.scheme() != "http" {
+        anyhow::bail!("Endpoint URL must use http or https, not {}", url.scheme());
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        anyhow::bail!("Endpoint URL needs a host, e.g. https://logs.example.com");
+    }
+    // Request paths are joined onto the endpoint, which replaces a last
+    // segment not ending in '/'
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url.into())
+}
+
+/// Write the config back in the format it was loaded from.
 pub fn save_config(config: &AppConfig) -> Result<()> {
     let path = config_path();
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    let json = serde_json::to_string_pretty(config)?;
-    fs::write(&path, json)?;
+    let contents = if is_toml(&path) {
+        match fs::read_to_string(&path) {
+            // Compact values, so an edited array stays on the one line it was on
+            Ok(existing) => merge_toml(&existing, &toml::to_string(config)?)?,
+            Err(_) => toml::to_string_pretty(config)?,
+        }
+    } else {
+        serde_json::to_string_pretty(config)?
+    };
+    fs::write(&path, contents)?;
+    Ok(())
+}
+
+/// `existing` TOML updated in place to the values in `updated`, keeping its
+/// comments, layout and key order.
+fn merge_toml(existing: &str, updated: &str) -> Result<String> {
+    let mut doc: DocumentMut = existing.parse()?;
+    let updated: DocumentMut = updated.parse()?;
+    merge_table(doc.as_table_mut(), updated.as_table());
+    Ok(doc.to_string())
+}
+
+fn merge_table(old: &mut dyn TableLike, new: &dyn TableLike) {
+    let stale: Vec<String> = old
+        .iter()
+        .filter(|(key, _)| !new.contains_key(key))
+        .map(|(key, _)| key.to_string())
+        .collect();
+    for key in stale {
+        old.remove(&key);
+    }
+    for (key, new_item) in new.iter() {
+        match old.get_mut(key) {
+            Some(old_item) if old_item.is_table_like() && new_item.is_table_like() => {
+                if let (Some(old_table), Some(new_table)) =
+                    (old_item.as_table_like_mut(), new_item.as_table_like())
+                {
+                    merge_table(old_table, new_table);
+                }
+                if let Some(inline) = old_item.as_inline_table_mut() {
+                    inline.fmt();
+                }
+            }
+            Some(toml_edit::Item::Value(old_value)) if let Some(new_value) = new_item.as_value() => {
+                // Keep the whitespace and trailing comment around the value
+                let decor = old_value.decor().clone();
+                *old_value = new_value.clone();
+                *old_value.decor_mut() = decor;
+            }
+            Some(old_item) => *old_item = new_item.clone(),
+            None => {
+                old.insert(key, new_item.clone());
+            }
+        }
+    }
+}
+
+fn session_path() -> PathBuf {
+    config_dir().join("session.json")
+}
+
+/// The last saved session, if there is one younger than `max_age_mins`.
+pub fn load_session(max_age_mins: u64) -> Option<Session> {
+    let contents = fs::read_to_string(session_path()).ok()?;
+    let session: Session = serde_json::from_str(&contents).ok()?;
+    let age_secs = chrono::Utc::now().timestamp() - session.saved_at;
+    (age_secs <= max_age_mins as i64 * 60).then_some(session)
+}
+
+pub fn save_session(session: &Session) -> Result<()> {
+    let path = session_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(session)?)?;
+    Ok(())
+}
+
+fn search_history_path() -> PathBuf {
+    config_dir().join("search_history.json")
+}
+
+/// Past searches, oldest first; empty if none were saved.
+pub fn load_search_history() -> Vec<String> {
+    fs::read_to_string(search_history_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_search_history(history: &[String]) -> Result<()> {
+    let path = search_history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(history)?)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_paths_get_a_trailing_slash() {
+        assert_eq!(normalize_endpoint("logs.example.com").unwrap(), "https://logs.example.com/");
+        assert_eq!(
+            normalize_endpoint("http://proxy.internal:8080/opensearch").unwrap(),
+            "http://proxy.internal:8080/opensearch/"
+        );
+        assert_eq!(
+            normalize_endpoint("https://proxy.internal/opensearch/").unwrap(),
+            "https://proxy.internal/opensearch/"
+        );
+    }
+
+    #[test]
+    fn request_urls_keep_the_endpoint_path() {
+        let endpoint = normalize_endpoint("https://proxy.internal/opensearch").unwrap();
+        let url = url::Url::parse(&endpoint).unwrap().join("logs-*/_search").unwrap();
+        assert_eq!(url.as_str(), "https://proxy.internal/opensearch/logs-*/_search");
+    }
+
+    #[test]
+    fn saving_toml_keeps_comments_and_layout() {
+        let existing = r#"# Production cluster
+endpoint_url = "https://logs.example.com/"
+
+# Shown first in the application dropdown
+favorite_apps = ["billing"] # the usual suspects
+fields = { message = "msg" }
+ca_cert_path = "/etc/ca.pem"
+"#;
+        let mut config = AppConfig::new("https://logs.example.com/".to_string(), Auth::None);
+        config.favorite_apps = vec!["billing".to_string(), "checkout".to_string()];
+        config.fields.message = "msg".to_string();
+        let updated = toml::to_string(&config).unwrap();
+
+        let merged = merge_toml(existing, &updated).unwrap();
+        assert!(merged.starts_with("# Production cluster\nendpoint_url = \"https://logs.example.com/\"\n"));
+        assert!(merged.contains(
+            "# Shown first in the application dropdown\nfavorite_apps = [\"billing\", \"checkout\"] # the usual suspects\n"
+        ));
+        assert!(merged.contains("fields = { message = \"msg\", timestamp = \"@timestamp\""));
+        assert!(!merged.contains("ca_cert_path"));
+
+        let reloaded: AppConfig = toml::from_str(&merged).unwrap();
+        assert_eq!(reloaded.favorite_apps, config.favorite_apps);
+        assert_eq!(reloaded.fields.message, "msg");
+    }
+}
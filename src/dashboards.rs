@@ -0,0 +1,94 @@
+use crate::opensearch::LogQuery;
+
+/// Link to OpenSearch Dashboards Discover showing the entries `q` matches,
+/// in the index pattern with id `index_pattern` if given.
+pub fn discover_url(base: &str, q: &LogQuery, index_pattern: Option<&str>) -> String {
+    let (from, to) = q.time_range.bounds();
+    let global = format!("(time:(from:{},to:{}))", rison_string(from), rison_string(to));
+    let index = index_pattern
+        .map(|id| format!("index:{},", rison_string(id)))
+        .unwrap_or_default();
+    let app = format!(
+        "({}query:(language:lucene,query:{}))",
+        index,
+        rison_string(&lucene_query(q))
+    );
+    format!(
+        "{}/app/discover#/?_g={}&_a={}",
+        base.trim_end_matches('/'),
+        encode(&global),
+        encode(&app)
+    )
+}
+
+/// The filters of `q` as a Lucene query string.
+fn lucene_query(q: &LogQuery) -> String {
+    let fields = &q.fields;
+    let mut clauses = vec![format!("{}:{}", fields.profile, quoted(&q.profile))];
+    if !q.applications.is_empty() {
+        clauses.push(any_of(&fields.application, &q.applications));
+    }
+    if !q.severities.is_empty() {
+        clauses.push(any_of(&q.severity_field, &q.severities));
+    }
+    if !q.exclude_severities.is_empty() {
+        clauses.push(format!("NOT {}", any_of(&q.severity_field, &q.exclude_severities)));
+    }
+    if let Some(thread) = &q.thread {
+        clauses.push(format!("{}:{}", fields.thread, quoted(thread)));
+    }
+    let search = |text: &str| {
+        let text = if q.search_exact { quoted(text) } else { text.to_string() };
+        match &q.search_field {
+            Some(field) => format!("{}:({})", field, text),
+            None => text,
+        }
+    };
+    if let Some((field, value)) = &q.pivot {
+        clauses.push(format!("{}:{}", field, quoted(value)));
+    }
+    if let Some(text) = &q.search {
+        clauses.push(format!("({})", search(text)));
+    }
+    if let Some(text) = &q.exclude {
+        clauses.push(format!("NOT ({})", search(text)));
+    }
+    clauses.join(" AND ")
+}
+
+/// `field` matching any of `values`.
+fn any_of(field: &str, values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| quoted(value)).collect();
+    format!("{}:({})", field, values.join(" OR "))
+}
+
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A string in Rison, the URL-friendly JSON Dashboards keeps its state in.
+fn rison_string(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./~".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('!', "!!").replace('\'', "!'"))
+    }
+}
+
+/// Percent-encode everything but unreserved characters and Rison syntax.
+fn encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~()',:!*".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
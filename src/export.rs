@@ -0,0 +1,186 @@
+use crate::opensearch::LogEntry;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Formats log entries can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// `[timestamp] LEVEL [logger] message` lines.
+    #[default]
+    Text,
+    /// The raw `_source` documents.
+    Json,
+    /// One row per entry with the columns in [`CSV_HEADER`].
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "log",
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "text",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+        }
+    }
+
+    /// The format after this one, for switching formats in the save prompt.
+    pub fn next(self) -> Self {
+        match self {
+            ExportFormat::Text => ExportFormat::Json,
+            ExportFormat::Json => ExportFormat::Csv,
+            ExportFormat::Csv => ExportFormat::Text,
+        }
+    }
+}
+
+/// The columns of CSV exports.
+pub const CSV_HEADER: [&str; 5] = ["timestamp", "severity", "logger", "application", "message"];
+
+/// `log` in the given format. Text and CSV include the stacktrace if `stacktraces` is set.
+/// Longer strings than `max_length` characters are cut, unless it is 0.
+pub fn format_entry(
+    log: &LogEntry,
+    format: ExportFormat,
+    stacktraces: bool,
+    max_length: usize,
+) -> String {
+    match format {
+        ExportFormat::Text => text_line(log, stacktraces, max_length),
+        ExportFormat::Json => {
+            let mut source = log.source.clone();
+            truncate_strings(&mut source, max_length);
+            serde_json::to_string_pretty(&source).unwrap_or_default()
+        }
+        ExportFormat::Csv => csv_string(std::slice::from_ref(log), stacktraces, max_length),
+    }
+}
+
+/// `logs` in the given format: text lines, CSV rows, or a JSON array of the
+/// raw documents with every field kept.
+pub fn format_entries(
+    logs: &[LogEntry],
+    format: ExportFormat,
+    stacktraces: bool,
+    max_length: usize,
+) -> String {
+    match format {
+        ExportFormat::Text => logs
+            .iter()
+            .map(|log| text_line(log, stacktraces, max_length))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ExportFormat::Json => {
+            let mut sources: Value = logs.iter().map(|log| log.source.clone()).collect();
+            truncate_strings(&mut sources, max_length);
+            serde_json::to_string_pretty(&sources).unwrap_or_default()
+        }
+        ExportFormat::Csv => csv_string(logs, stacktraces, max_length),
+    }
+}
+
+fn csv_string(logs: &[LogEntry], stacktraces: bool, max_length: usize) -> String {
+    let mut out = Vec::new();
+    // Writing to a `Vec` cannot fail.
+    let _ = write_csv(logs, stacktraces, max_length, &mut out);
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// Write `logs` to `out` as CSV with a header row and the columns in
+/// [`CSV_HEADER`]. The stacktrace is appended to the message cell if
+/// `stacktraces` is set, so every entry stays one record.
+pub fn write_csv(
+    logs: &[LogEntry],
+    stacktraces: bool,
+    max_length: usize,
+    mut out: impl Write,
+) -> io::Result<()> {
+    writeln!(out, "{}", CSV_HEADER.join(","))?;
+    for log in logs {
+        let mut message = truncate(&log.message, max_length).into_owned();
+        if stacktraces && !log.stacktrace.is_empty() {
+            message.push('\n');
+            message.push_str(&truncate(&log.stacktrace, max_length));
+        }
+        let cells = [&log.timestamp, &log.severity, &log.logger, &log.application, &message];
+        let row: Vec<Cow<str>> = cells.iter().map(|cell| csv_cell(cell)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// `text` as a CSV cell: quoted, with quotes doubled, if it contains a comma,
+/// quote or line break.
+fn csv_cell(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// A log entry as a `[timestamp] LEVEL [logger] message` line, optionally
+/// followed by its stacktrace, each cut to `max_length` characters.
+pub fn text_line(log: &LogEntry, stacktraces: bool, max_length: usize) -> String {
+    let mut line = format!(
+        "[{}] {} [{}] {}",
+        log.timestamp,
+        log.severity,
+        log.logger,
+        truncate(&log.message, max_length)
+    );
+    if stacktraces && !log.stacktrace.is_empty() {
+        line.push('\n');
+        line.push_str(&truncate(&log.stacktrace, max_length));
+    }
+    line
+}
+
+/// `text` cut to `max_length` characters with a marker saying how much was
+/// dropped. A `max_length` of 0 keeps everything.
+pub fn truncate(text: &str, max_length: usize) -> Cow<'_, str> {
+    match text.char_indices().nth(max_length) {
+        Some((end, _)) if max_length > 0 => {
+            let dropped = text[end..].chars().count();
+            Cow::Owned(format!("{}… [{} more chars]", &text[..end], dropped))
+        }
+        _ => Cow::Borrowed(text),
+    }
+}
+
+fn truncate_strings(value: &mut Value, max_length: usize) {
+    match value {
+        Value::String(s) => {
+            if let Cow::Owned(short) = truncate(s, max_length) {
+                *s = short;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| truncate_strings(v, max_length)),
+        Value::Object(map) => map.values_mut().for_each(|v| truncate_strings(v, max_length)),
+        _ => {}
+    }
+}
+
+/// Write `contents` to `path`, expanding a leading `~/`. Returns the path written.
+pub fn write_file(path: &str, contents: &str) -> Result<PathBuf> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => {
+            let home = std::env::var("HOME").context("HOME is not set")?;
+            PathBuf::from(home).join(rest)
+        }
+        None => PathBuf::from(path),
+    };
+    fs::write(&path, contents).with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(path)
+}
@@ -1,3 +1,6 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
 /// Reusable filterable dropdown field.
 ///
 /// Holds a list of items, a type-to-filter search string, and tracks both
@@ -7,12 +10,17 @@ pub struct FilterField {
     items: Vec<String>,
     /// Index into `items` of the confirmed (committed) selection.
     selected_index: usize,
+    /// Indices into `items` picked together; when any are, they replace
+    /// `selected_index` as the selection.
+    selected: HashSet<usize>,
     /// Current search/filter text typed by the user.
     filter_text: String,
     /// Indices into `items` that match `filter_text`.
     filtered_indices: Vec<usize>,
     /// Cursor position within `filtered_indices`.
     cursor: usize,
+    /// Number of leading `items` pinned above a separator.
+    pinned: usize,
 }
 
 impl FilterField {
@@ -20,23 +28,77 @@ impl FilterField {
         Self {
             items: Vec::new(),
             selected_index: 0,
+            selected: HashSet::new(),
             filter_text: String::new(),
             filtered_indices: Vec::new(),
             cursor: 0,
+            pinned: 0,
         }
     }
 
     pub fn set_items(&mut self, items: Vec<String>) {
+        self.set_pinned_items(items, 0);
+    }
+
+    /// Set the items, keeping the first `pinned` of them above a separator.
+    pub fn set_pinned_items(&mut self, items: Vec<String>, pinned: usize) {
         self.items = items;
+        self.pinned = pinned;
         self.selected_index = 0;
+        self.selected.clear();
         self.refilter();
     }
 
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
     /// Select the item matching `value`, if present.
-    pub fn select_value(&mut self, value: &str) {
-        if let Some(idx) = self.items.iter().position(|item| item == value) {
+    pub fn select_value(&mut self, value: &str) -> bool {
+        let found = self.items.iter().position(|item| item == value);
+        if let Some(idx) = found {
             self.selected_index = idx;
+            self.selected.clear();
         }
+        found.is_some()
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected_index = 0;
+        self.selected.clear();
+    }
+
+    /// Add the item matching `value` to the items picked together, if present.
+    pub fn check_value(&mut self, value: &str) -> bool {
+        let found = self.items.iter().position(|item| item == value);
+        if let Some(idx) = found {
+            self.selected.insert(idx);
+        }
+        found.is_some()
+    }
+
+    /// Add the highlighted item to the items picked together, or take it out.
+    pub fn toggle_highlighted(&mut self) {
+        if let Some(&idx) = self.filtered_indices.get(self.cursor)
+            && !self.selected.remove(&idx)
+        {
+            self.selected.insert(idx);
+        }
+    }
+
+    pub fn clear_checked(&mut self) {
+        self.selected.clear();
+    }
+
+    /// The items picked together, in list order.
+    pub fn checked_values(&self) -> Vec<&str> {
+        let mut indices: Vec<usize> = self.selected.iter().copied().collect();
+        indices.sort_unstable();
+        indices.into_iter().map(|i| self.items[i].as_str()).collect()
+    }
+
+    pub fn is_checked(&self, value: &str) -> bool {
+        self.selected.iter().any(|&i| self.items[i] == value)
     }
 
     /// The confirmed/committed value shown in the filter bar.
@@ -44,6 +106,24 @@ impl FilterField {
         self.items.get(self.selected_index).map(|s| s.as_str())
     }
 
+    /// The item under the cursor in the open dropdown.
+    pub fn highlighted_value(&self) -> Option<&str> {
+        self.filtered_indices
+            .get(self.cursor)
+            .map(|&i| self.items[i].as_str())
+    }
+
+    /// Move the cursor onto the item matching `value`, if it is visible.
+    pub fn highlight_value(&mut self, value: &str) {
+        if let Some(pos) = self
+            .filtered_indices
+            .iter()
+            .position(|&i| self.items[i] == value)
+        {
+            self.cursor = pos;
+        }
+    }
+
     /// Called when the dropdown opens: reset filter, position cursor on current selection.
     pub fn open(&mut self) {
         self.filter_text.clear();
@@ -72,14 +152,37 @@ impl FilterField {
         self.refilter();
     }
 
+    /// Move the cursor down, wrapping from the last item to the first.
     pub fn next(&mut self) {
         if !self.filtered_indices.is_empty() {
-            self.cursor = (self.cursor + 1).min(self.filtered_indices.len() - 1);
+            self.cursor = (self.cursor + 1) % self.filtered_indices.len();
         }
     }
 
+    /// Move the cursor up, wrapping from the first item to the last.
     pub fn previous(&mut self) {
-        self.cursor = self.cursor.saturating_sub(1);
+        self.cursor = match self.cursor {
+            0 => self.filtered_indices.len().saturating_sub(1),
+            cursor => cursor - 1,
+        };
+    }
+
+    pub fn first(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn last(&mut self) {
+        self.cursor = self.filtered_indices.len().saturating_sub(1);
+    }
+
+    /// Move the cursor `rows` items down, stopping at the last one.
+    pub fn page_down(&mut self, rows: usize) {
+        self.cursor = (self.cursor + rows).min(self.filtered_indices.len().saturating_sub(1));
+    }
+
+    /// Move the cursor `rows` items up, stopping at the first one.
+    pub fn page_up(&mut self, rows: usize) {
+        self.cursor = self.cursor.saturating_sub(rows);
     }
 
     pub fn filter_text(&self) -> &str {
@@ -97,19 +200,151 @@ impl FilterField {
         self.cursor
     }
 
+    /// Position within `filtered_items()` where the pinned items end, if both
+    /// pinned and unpinned items are currently visible.
+    pub fn pinned_boundary(&self) -> Option<usize> {
+        let visible_pinned = self
+            .filtered_indices
+            .iter()
+            .filter(|&&i| i < self.pinned)
+            .count();
+        (visible_pinned > 0 && visible_pinned < self.filtered_indices.len()).then_some(visible_pinned)
+    }
+
+    /// Narrow the items to those fuzzily matching `filter_text`, best match
+    /// first within the pinned and the other items, with the cursor on the
+    /// best one.
     fn refilter(&mut self) {
-        let query = self.filter_text.to_lowercase();
-        self.filtered_indices = self
+        if self.filter_text.is_empty() {
+            self.filtered_indices = (0..self.items.len()).collect();
+            self.cursor = self.cursor.min(self.filtered_indices.len().saturating_sub(1));
+            return;
+        }
+        let mut scored: Vec<(usize, i64)> = self
             .items
             .iter()
             .enumerate()
-            .filter(|(_, item)| query.is_empty() || item.to_lowercase().contains(&query))
-            .map(|(i, _)| i)
+            .filter_map(|(i, item)| fuzzy_score(item, &self.filter_text).map(|score| (i, score)))
             .collect();
-        if self.filtered_indices.is_empty() {
-            self.cursor = 0;
-        } else {
-            self.cursor = self.cursor.min(self.filtered_indices.len() - 1);
+        // Shorter items first among equally good matches, which are closer to
+        // the query; stable, so ties beyond that keep their order
+        scored.sort_by_key(|&(i, score)| {
+            (i >= self.pinned, Reverse(score), self.items[i].chars().count())
+        });
+        self.cursor = scored
+            .iter()
+            .enumerate()
+            .max_by_key(|&(pos, &(_, score))| (score, Reverse(pos)))
+            .map_or(0, |(pos, _)| pos);
+        self.filtered_indices = scored.into_iter().map(|(i, _)| i).collect();
+    }
+}
+
+/// How well `item` matches `query` as a case-insensitive subsequence, or
+/// `None` if it doesn't. Consecutive characters, characters at word starts,
+/// the query appearing as a whole and matching from the very start score
+/// extra; gaps cost.
+fn fuzzy_score(item: &str, query: &str) -> Option<i64> {
+    let positions = match_positions(item, query)?;
+    let chars: Vec<char> = item.chars().collect();
+    let mut score = 0;
+    for (n, &at) in positions.iter().enumerate() {
+        score += match n.checked_sub(1).map(|prev| positions[prev]) {
+            Some(prev) if at == prev + 1 => 5,
+            Some(prev) => -((at - prev - 1).min(10) as i64),
+            None => 0,
+        };
+        if at == 0 || matches!(chars[at - 1], '-' | '_' | '.' | '/' | ' ') {
+            score += 3;
+        }
+    }
+    if positions.windows(2).all(|pair| pair[1] == pair[0] + 1) {
+        score += 20;
+    }
+    if positions.first() == Some(&0) {
+        score += 10;
+    }
+    Some(score)
+}
+
+/// Character positions in `item` matching `query` case-insensitively: where
+/// it appears as a whole if it does, otherwise the earliest subsequence.
+/// `None` if it doesn't match at all.
+pub fn match_positions(item: &str, query: &str) -> Option<Vec<usize>> {
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+    let item: Vec<char> = item.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+    if query.is_empty() {
+        return Some(Vec::new());
+    }
+    if let Some(start) = item
+        .windows(query.len())
+        .position(|window| window.iter().zip(&query).all(|(&a, &b)| same(a, b)))
+    {
+        return Some((start..start + query.len()).collect());
+    }
+    let mut positions = Vec::with_capacity(query.len());
+    let mut from = 0;
+    for &c in &query {
+        let at = from + item[from..].iter().position(|&ic| same(ic, c))?;
+        positions.push(at);
+        from = at + 1;
+    }
+    Some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked(items: &[&str], query: &str) -> Vec<String> {
+        let mut field = FilterField::new();
+        field.set_items(items.iter().map(|s| s.to_string()).collect());
+        field.open();
+        for c in query.chars() {
+            field.type_char(c);
         }
+        field.filtered_items().into_iter().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn prefix_beats_substring_beats_scattered() {
+        let prefix = fuzzy_score("billing", "bill").unwrap();
+        let word_start = fuzzy_score("legacy-billing", "bill").unwrap();
+        let substring = fuzzy_score("autobilling", "bill").unwrap();
+        let scattered = fuzzy_score("b-i-l-l", "bill").unwrap();
+        assert!(prefix > word_start, "{} > {}", prefix, word_start);
+        assert!(word_start > substring, "{} > {}", word_start, substring);
+        assert!(substring > scattered, "{} > {}", substring, scattered);
+    }
+
+    #[test]
+    fn shorter_items_rank_first_among_equal_matches() {
+        assert_eq!(
+            ranked(&["billing-service", "b-i-l-l", "autobilling", "billing", "checkout"], "bill"),
+            ["billing", "billing-service", "autobilling", "b-i-l-l"]
+        );
+        assert_eq!(
+            ranked(&["api-gateway-v2", "api-gw", "api-gateway"], "api"),
+            ["api-gw", "api-gateway", "api-gateway-v2"]
+        );
+    }
+
+    #[test]
+    fn cursor_starts_on_the_best_match() {
+        let mut field = FilterField::new();
+        field.set_items(vec!["autobilling".to_string(), "billing".to_string()]);
+        field.open();
+        for c in "bill".chars() {
+            field.type_char(c);
+        }
+        assert_eq!(field.highlighted_value(), Some("billing"));
+    }
+
+    #[test]
+    fn match_positions_prefer_a_contiguous_run() {
+        assert_eq!(match_positions("b-bill", "bill"), Some(vec![2, 3, 4, 5]));
+        assert_eq!(match_positions("b-i-l-l", "BILL"), Some(vec![0, 2, 4, 6]));
+        assert_eq!(match_positions("billing", "bx"), None);
     }
 }
@@ -1,26 +1,36 @@
 mod app;
 mod config;
+mod dashboards;
+mod export;
 mod filter_field;
 mod opensearch;
+mod search;
 mod ui;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use app::{App, Pane, CONTEXT_MENU_OPTIONS};
 use arboard::Clipboard;
-use config::AppConfig;
-use crossterm::event::{self, Event, KeyCode};
+use config::{AppConfig, Auth, EnterAction, FilterState};
+use export::ExportFormat;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
+use opensearch::LogEntry;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use std::io;
-use std::process::Command;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::process::Command;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = match config::load_config() {
+    let filters = filters_arg()?;
+    let fresh = std::env::args().any(|arg| arg == "--fresh");
+
+    let mut config = match config::load_config() {
         Ok(Some(cfg)) => cfg,
         Ok(None) => {
             match run_setup_dialog(None)? {
@@ -36,7 +46,11 @@ async fn main() -> Result<()> {
         }
     };
 
+    if std::env::args().any(|arg| arg == "--read-only") {
+        config.read_only = true;
+    }
     let mut app = App::new(config);
+    app.search_history = config::load_search_history();
 
     // Setup terminal
     enable_raw_mode()?;
@@ -48,8 +62,28 @@ async fn main() -> Result<()> {
     // Show loading state, then fetch filters
     terminal.draw(|f| ui::render(f, &app))?;
     app.load_filters().await;
-    terminal.draw(|f| ui::render(f, &app))?;
-    app.fetch_logs().await;
+    let session = match &filters {
+        None if app.config.restore_session && !fresh => {
+            config::load_session(app.config.session_max_age_mins)
+        }
+        _ => None,
+    };
+    if let Some(filters) = filters {
+        let missing = app.apply_filter_state(&filters);
+        if !missing.is_empty() {
+            app.notice = Some(format!("--filters values not available: {}", missing.join(", ")));
+        }
+        app.fetch_logs();
+    } else if let Some(session) = session {
+        let missing = app.restore_session(&session);
+        app.notice = Some(if missing.is_empty() {
+            "restored last session (0 resets filters)".to_string()
+        } else {
+            format!("restored last session; not available: {}", missing.join(", "))
+        });
+    } else {
+        app.fetch_logs();
+    }
 
     // Main loop
     let result = run(&mut terminal, &mut app).await;
@@ -59,16 +93,127 @@ async fn main() -> Result<()> {
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
+    if app.config.restore_session
+        && !app.config.read_only
+        && let Err(e) = config::save_session(&app.session())
+    {
+        eprintln!("Cannot save session: {}", e);
+    }
+    if !app.config.read_only
+        && let Err(e) = config::save_search_history(&app.search_history)
+    {
+        eprintln!("Cannot save search history: {}", e);
+    }
+    if !app.config.read_only
+        && let Some(last) = app.last_filters()
+        && last != app.config.last_filters
+    {
+        app.config.last_filters = last;
+        if let Err(e) = config::save_config(&app.config) {
+            eprintln!("Cannot save the last filters: {}", e);
+        }
+    }
+
     result
 }
 
+/// Filter state passed with `--filters <file-or-json>` (or `--filters-json
+/// <json>`), applied once the filters are loaded.
+fn filters_arg() -> Result<Option<FilterState>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg != "--filters" && arg != "--filters-json" {
+            continue;
+        }
+        let value = args.next().with_context(|| format!("{} needs an argument", arg))?;
+        let json = if arg == "--filters" && !value.trim_start().starts_with('{') {
+            std::fs::read_to_string(&value)
+                .with_context(|| format!("Cannot read filters file {}", value))?
+        } else {
+            value
+        };
+        let filters = serde_json::from_str(&json).with_context(|| format!("Invalid {}", arg))?;
+        return Ok(Some(filters));
+    }
+    Ok(None)
+}
+
+/// Fields of the setup dialog.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SetupField {
+    Url,
+    Auth,
+    Region,
+    Username,
+    Password,
+    ApiKeyId,
+    ApiKey,
+}
+
+/// Authentication modes offered by the setup dialog.
+const AUTH_MODES: &[&str] = &["AWS SigV4", "Basic", "API key", "None"];
+
 struct SetupState {
     url: String,
+    auth_mode: usize, // index into AUTH_MODES
     region: String,
-    active_field: usize, // 0 = URL, 1 = Region
+    username: String,
+    password: String,
+    api_key_id: String,
+    api_key: String,
+    active_field: usize, // index into fields()
     error_message: Option<String>,
 }
 
+impl SetupState {
+    /// The fields shown for the selected auth mode.
+    fn fields(&self) -> &'static [SetupField] {
+        match self.auth_mode {
+            0 => &[SetupField::Url, SetupField::Auth, SetupField::Region],
+            1 => &[SetupField::Url, SetupField::Auth, SetupField::Username, SetupField::Password],
+            2 => &[SetupField::Url, SetupField::Auth, SetupField::ApiKeyId, SetupField::ApiKey],
+            _ => &[SetupField::Url, SetupField::Auth],
+        }
+    }
+
+    fn active(&self) -> SetupField {
+        self.fields()[self.active_field]
+    }
+
+    fn text_mut(&mut self, field: SetupField) -> Option<&mut String> {
+        match field {
+            SetupField::Url => Some(&mut self.url),
+            SetupField::Region => Some(&mut self.region),
+            SetupField::Username => Some(&mut self.username),
+            SetupField::Password => Some(&mut self.password),
+            SetupField::ApiKeyId => Some(&mut self.api_key_id),
+            SetupField::ApiKey => Some(&mut self.api_key),
+            SetupField::Auth => None,
+        }
+    }
+
+    fn cycle_auth(&mut self, forward: bool) {
+        let n = AUTH_MODES.len();
+        self.auth_mode = if forward { (self.auth_mode + 1) % n } else { (self.auth_mode + n - 1) % n };
+    }
+
+    fn auth(&self) -> Auth {
+        match self.auth_mode {
+            // An empty region is left to the AWS environment and profile
+            0 => Auth::Aws { region: self.region.trim().to_string() },
+            1 => Auth::Basic {
+                username: self.username.trim().to_string(),
+                password: self.password.clone(),
+            },
+            2 => Auth::ApiKey {
+                id: self.api_key_id.trim().to_string(),
+                key: self.api_key.trim().to_string(),
+            },
+            _ => Auth::None,
+        }
+    }
+}
+
 fn run_setup_dialog(error: Option<&str>) -> Result<Option<AppConfig>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -78,7 +223,12 @@ fn run_setup_dialog(error: Option<&str>) -> Result<Option<AppConfig>> {
 
     let mut state = SetupState {
         url: String::new(),
+        auth_mode: 0,
         region: "eu-central-1".to_string(),
+        username: String::new(),
+        password: String::new(),
+        api_key_id: String::new(),
+        api_key: String::new(),
         active_field: 0,
         error_message: error.map(String::from),
     };
@@ -86,49 +236,63 @@ fn run_setup_dialog(error: Option<&str>) -> Result<Option<AppConfig>> {
     let result = loop {
         terminal.draw(|f| render_setup_dialog(f, &state))?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Tab | KeyCode::Down => {
-                        state.active_field = (state.active_field + 1) % 2;
-                    }
-                    KeyCode::BackTab | KeyCode::Up => {
-                        state.active_field = if state.active_field == 0 { 1 } else { 0 };
+        if event::poll(std::time::Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+        {
+            let count = state.fields().len();
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => {
+                    state.active_field = (state.active_field + 1) % count;
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    state.active_field = (state.active_field + count - 1) % count;
+                }
+                KeyCode::Left | KeyCode::Right | KeyCode::Char(' ')
+                    if state.active() == SetupField::Auth =>
+                {
+                    state.cycle_auth(key.code != KeyCode::Left);
+                }
+                KeyCode::Char(c) => {
+                    if let Some(text) = state.text_mut(state.active()) {
+                        text.push(c);
                     }
-                    KeyCode::Char(c) => {
-                        match state.active_field {
-                            0 => state.url.push(c),
-                            _ => state.region.push(c),
-                        }
+                }
+                KeyCode::Backspace => {
+                    if let Some(text) = state.text_mut(state.active()) {
+                        text.pop();
                     }
-                    KeyCode::Backspace => {
-                        match state.active_field {
-                            0 => { state.url.pop(); }
-                            _ => { state.region.pop(); }
+                }
+                KeyCode::Enter if !state.url.is_empty() => {
+                    match config::normalize_endpoint(&state.url) {
+                        Ok(url) => state.url = url,
+                        Err(e) => {
+                            state.error_message = Some(e.to_string());
+                            continue;
                         }
                     }
-                    KeyCode::Enter => {
-                        if !state.url.is_empty() {
-                            let cfg = AppConfig {
-                                endpoint_url: state.url.clone(),
-                                aws_region: if state.region.is_empty() {
-                                    "eu-central-1".to_string()
-                                } else {
-                                    state.region.clone()
-                                },
-                            };
-                            if let Err(e) = config::save_config(&cfg) {
-                                state.error_message = Some(format!("Failed to save config: {}", e));
-                            } else {
-                                break Some(cfg);
-                            }
+                    let missing = match state.auth() {
+                        Auth::Basic { username, .. } if username.is_empty() => Some("a username"),
+                        Auth::ApiKey { id, key } if id.is_empty() || key.is_empty() => {
+                            Some("an id and a key")
                         }
+                        _ => None,
+                    };
+                    if let Some(missing) = missing {
+                        let mode = AUTH_MODES[state.auth_mode];
+                        state.error_message = Some(format!("{} auth needs {}", mode, missing));
+                        continue;
                     }
-                    KeyCode::Esc => {
-                        break None;
+                    let cfg = AppConfig::new(state.url.clone(), state.auth());
+                    if let Err(e) = config::save_config(&cfg) {
+                        state.error_message = Some(format!("Failed to save config: {}", e));
+                    } else {
+                        break Some(cfg);
                     }
-                    _ => {}
                 }
+                KeyCode::Esc => {
+                    break None;
+                }
+                _ => {}
             }
         }
     };
@@ -146,7 +310,7 @@ fn render_setup_dialog(f: &mut Frame, state: &SetupState) {
 
     let area = f.area();
     let width = 60_u16.min(area.width.saturating_sub(4));
-    let height = 14_u16.min(area.height.saturating_sub(2));
+    let height = 18_u16.min(area.height.saturating_sub(2));
     let x = area.x + (area.width.saturating_sub(width)) / 2;
     let y = area.y + (area.height.saturating_sub(height)) / 2;
     let popup = Rect::new(x, y, width, height);
@@ -168,56 +332,52 @@ fn render_setup_dialog(f: &mut Frame, state: &SetupState) {
         lines.push(Line::from(""));
     }
 
-    // URL field
-    let url_label_style = if state.active_field == 0 {
-        Style::default().fg(Color::Cyan).bold()
-    } else {
-        Style::default().fg(Color::White)
-    };
-    lines.push(Line::from(Span::styled("OpenSearch Endpoint URL:", url_label_style)));
-
-    let url_line = if state.active_field == 0 {
-        Line::from(vec![
-            Span::styled("> ", Style::default().fg(Color::Yellow)),
-            Span::raw(&state.url),
-            Span::styled("█", Style::default().fg(Color::Cyan)),
-        ])
-    } else {
-        Line::from(vec![
-            Span::styled("  ", Style::default()),
-            Span::raw(&state.url),
-        ])
-    };
-    lines.push(url_line);
-    lines.push(Line::from(""));
+    for (i, &field) in state.fields().iter().enumerate() {
+        let active = i == state.active_field;
+        let label_style = if active {
+            Style::default().fg(Color::Cyan).bold()
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let (label, value) = match field {
+            SetupField::Url => ("OpenSearch Endpoint URL:", state.url.clone()),
+            SetupField::Auth if active => ("Authentication:", format!("< {} >", AUTH_MODES[state.auth_mode])),
+            SetupField::Auth => ("Authentication:", AUTH_MODES[state.auth_mode].to_string()),
+            SetupField::Region => ("AWS Region:", state.region.clone()),
+            SetupField::Username => ("Username:", state.username.clone()),
+            SetupField::Password => ("Password:", "•".repeat(state.password.chars().count())),
+            SetupField::ApiKeyId => ("API Key ID:", state.api_key_id.clone()),
+            SetupField::ApiKey => ("API Key:", "•".repeat(state.api_key.chars().count())),
+        };
+        lines.push(Line::from(Span::styled(label, label_style)));
 
-    // Region field
-    let region_label_style = if state.active_field == 1 {
-        Style::default().fg(Color::Cyan).bold()
-    } else {
-        Style::default().fg(Color::White)
-    };
-    lines.push(Line::from(Span::styled("AWS Region:", region_label_style)));
-
-    let region_line = if state.active_field == 1 {
-        Line::from(vec![
-            Span::styled("> ", Style::default().fg(Color::Yellow)),
-            Span::raw(&state.region),
-            Span::styled("█", Style::default().fg(Color::Cyan)),
-        ])
-    } else {
-        Line::from(vec![
-            Span::styled("  ", Style::default()),
-            Span::raw(&state.region),
-        ])
-    };
-    lines.push(region_line);
-    lines.push(Line::from(""));
+        let value_line = if active && field != SetupField::Auth {
+            Line::from(vec![
+                Span::styled("> ", Style::default().fg(Color::Yellow)),
+                Span::raw(value),
+                Span::styled("█", Style::default().fg(Color::Cyan)),
+            ])
+        } else if active {
+            Line::from(vec![
+                Span::styled("> ", Style::default().fg(Color::Yellow)),
+                Span::raw(value),
+            ])
+        } else {
+            Line::from(vec![
+                Span::styled("  ", Style::default()),
+                Span::raw(value),
+            ])
+        };
+        lines.push(value_line);
+        lines.push(Line::from(""));
+    }
 
     // Help text
     lines.push(Line::from(vec![
         Span::styled(" Tab ", Style::default().fg(Color::Yellow).bold()),
         Span::raw("switch field  "),
+        Span::styled(" ←/→ ", Style::default().fg(Color::Yellow).bold()),
+        Span::raw("auth  "),
         Span::styled(" Enter ", Style::default().fg(Color::Yellow).bold()),
         Span::raw("confirm  "),
         Span::styled(" Esc ", Style::default().fg(Color::Yellow).bold()),
@@ -233,28 +393,161 @@ fn render_setup_dialog(f: &mut Frame, state: &SetupState) {
     f.render_widget(Paragraph::new(lines), inner);
 }
 
-fn open_in_editor(
+/// The message of `log`, followed by its stacktrace if it has one, each cut
+/// to `max_length` characters unless it is 0.
+fn entry_text(log: &LogEntry, max_length: usize) -> String {
+    let mut text = export::truncate(&log.message, max_length).into_owned();
+    if !log.stacktrace.is_empty() {
+        text.push('\n');
+        text.push_str(&export::truncate(&log.stacktrace, max_length));
+    }
+    text
+}
+
+/// Put `text` on the system clipboard, returning a status message.
+fn copy_to_clipboard(text: String) -> String {
+    match Clipboard::new().and_then(|mut cb| cb.set_text(text)) {
+        Ok(_) => "Copied to clipboard".to_string(),
+        Err(e) => format!("Clipboard error: {}", e),
+    }
+}
+
+/// Open `url` in the default browser, returning a status message.
+async fn open_url(url: &str) -> String {
+    let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+    let status = Command::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+    match status {
+        Ok(status) if status.success() => format!("Opened {}", url),
+        Ok(status) => describe_exit(opener, status),
+        Err(e) => format!("Failed to run {}: {}", opener, e),
+    }
+}
+
+/// Launchers that hand the file to another application and return at once,
+/// so the TUI doesn't need to be suspended for them.
+const DETACHED_EDITORS: &[&str] = &["open", "xdg-open", "gio"];
+
+/// How long a detached launcher may take to hand the file over; the TUI
+/// stays in raw mode and unresponsive while it runs.
+const DETACHED_EDITOR_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `$EDITOR` command line, defaulting to the system's `open` launcher.
+fn editor_command() -> String {
+    std::env::var("EDITOR").unwrap_or_else(|_| "open".to_string())
+}
+
+/// The name of the configured editor if it is a detached launcher, which
+/// returns before the file has been edited.
+fn detached_editor() -> Option<String> {
+    let editor = editor_command();
+    let program = editor.split_whitespace().next()?;
+    let name = program.rsplit('/').next().unwrap_or(program);
+    DETACHED_EDITORS.contains(&name).then(|| name.to_string())
+}
+
+/// How an editor round-trip ended.
+struct EditorRun {
+    status: String,
+    /// The editor exited cleanly after the user was done with the file, so
+    /// it is worth reading back.
+    completed: bool,
+}
+
+impl EditorRun {
+    fn failed(status: String) -> Self {
+        EditorRun { status, completed: false }
+    }
+}
+
+/// Open `content` in `$EDITOR` (which may carry arguments, e.g. `code --wait`),
+/// returning a status message. Terminal editors get the terminal until they
+/// exit or Ctrl-C is pressed, after which the TUI is restored either way.
+async fn open_in_editor(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     content: &str,
     filename: &str,
-) -> Result<String> {
+    timeout_mins: u64,
+) -> Result<EditorRun> {
     let tmp = std::env::temp_dir().join(filename);
     std::fs::write(&tmp, content)?;
 
-    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "open".to_string());
+    let editor = editor_command();
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        return Ok(EditorRun::failed("EDITOR is empty".to_string()));
+    };
+    let mut command = Command::new(program);
+    command.args(words).arg(&tmp);
+
+    let name = program.rsplit('/').next().unwrap_or(program);
+    if DETACHED_EDITORS.contains(&name) {
+        command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        let status = tokio::time::timeout(DETACHED_EDITOR_TIMEOUT, command.kill_on_drop(true).status());
+        // Never completed: the launcher returns before the file is edited
+        return Ok(EditorRun::failed(match status.await {
+            Ok(Ok(status)) if status.success() => format!("Opened with {}", name),
+            Ok(Ok(status)) => describe_exit(name, status),
+            Ok(Err(e)) => format!("Failed to open editor {}: {}", name, e),
+            Err(_) => format!("{} didn't return within {}s", name, DETACHED_EDITOR_TIMEOUT.as_secs()),
+        }));
+    }
+
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
 
-    let result = Command::new(&editor).arg(&tmp).status();
+    // Waits forever when no timeout is configured
+    let deadline = async {
+        match timeout_mins {
+            0 => std::future::pending().await,
+            mins => tokio::time::sleep(Duration::from_secs(mins * 60)).await,
+        }
+    };
+    let mut timed_out = false;
+    let result = match command.kill_on_drop(true).spawn() {
+        Ok(mut child) => tokio::select! {
+            status = child.wait() => status.map(Some),
+            _ = tokio::signal::ctrl_c() => child.kill().await.map(|_| None),
+            _ = deadline => {
+                timed_out = true;
+                child.kill().await.map(|_| None)
+            }
+        },
+        Err(e) => Err(e),
+    };
 
     enable_raw_mode()?;
     execute!(terminal.backend_mut(), EnterAlternateScreen)?;
     terminal.clear()?;
 
-    match result {
-        Ok(s) if s.success() => Ok("Editor closed".to_string()),
-        Ok(s) => Ok(format!("Editor exited: {}", s)),
-        Err(e) => Ok(format!("Failed to open editor: {}", e)),
+    Ok(match result {
+        Ok(Some(status)) if status.success() => {
+            EditorRun { status: "Editor closed".to_string(), completed: true }
+        }
+        Ok(Some(status)) => EditorRun::failed(describe_exit(name, status)),
+        Ok(None) if timed_out => EditorRun::failed(format!("Closed {} after {} min", name, timeout_mins)),
+        Ok(None) => EditorRun::failed(format!("Cancelled {}", name)),
+        Err(e) => EditorRun::failed(format!("Failed to open editor {}: {}", name, e)),
+    })
+}
+
+/// Status message for an editor that didn't exit cleanly.
+fn describe_exit(name: &str, status: ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("{} was killed by signal {}", name, signal);
+        }
+    }
+    match status.code() {
+        Some(code) => format!("{} exited with code {}", name, code),
+        None => format!("{} exited abnormally", name),
     }
 }
 
@@ -263,196 +556,525 @@ async fn run(
     app: &mut App,
 ) -> Result<()> {
     loop {
+        app.poll_fetches();
+        app.tick();
         terminal.draw(|f| ui::render(f, app))?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match app.focused {
-                    // --- Logs pane focused ---
-                    Pane::Logs => match key.code {
-                        KeyCode::Char('q') => return Ok(()),
-                        KeyCode::Char('P') => {
-                            app.profile_filter.open();
-                            app.focused = Pane::Profile;
-                        }
-                        KeyCode::Char('A') => {
-                            app.app_filter.open();
-                            app.focused = Pane::Application;
-                        }
-                        KeyCode::Char('S') => {
-                            app.severity_filter.open();
-                            app.focused = Pane::Severity;
-                        }
-                        KeyCode::Char('T') => {
-                            app.time_filter.open();
-                            app.focused = Pane::TimeRange;
-                        }
-                        KeyCode::Char('N') => {
-                            app.limit_filter.open();
-                            app.focused = Pane::Limit;
-                        }
-                        KeyCode::Char('R') => {
-                            app.fetch_page(app.page).await;
-                        }
-                        KeyCode::Down | KeyCode::Char('j') => app.scroll_down(),
-                        KeyCode::Up | KeyCode::Char('k') => app.scroll_up(),
-                        KeyCode::Right | KeyCode::Char('l') => {
-                            app.next_page().await;
-                        }
-                        KeyCode::Left | KeyCode::Char('h') => {
-                            app.prev_page().await;
-                        }
-                        KeyCode::Enter => {
-                            if !app.logs.is_empty() {
-                                app.context_cursor = 0;
-                                app.focused = Pane::LogContext;
-                            }
-                        }
-                        KeyCode::Char('/') => {
-                            app.focused = Pane::Search;
+        if event::poll(std::time::Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+        {
+            if app.key_pressed() {
+                continue;
+            }
+            match app.focused {
+                // --- Logs pane focused ---
+                Pane::Logs => match key.code {
+                    KeyCode::Char('q') => return Ok(()),
+                    // Clipboard, editor and config writes
+                    KeyCode::Char('y' | 'J' | 'U' | 'E' | 'O' | 'W' | 'm' | 'Q') if app.config.read_only => {
+                        app.refuse_read_only();
+                    }
+                    KeyCode::Char('P') => {
+                        app.profile_filter.open();
+                        app.focused = Pane::Profile;
+                    }
+                    KeyCode::Char('A') => {
+                        app.app_filter.open();
+                        app.focused = Pane::Application;
+                    }
+                    KeyCode::Char('S') => {
+                        app.severity_filter.open();
+                        app.focused = Pane::Severity;
+                    }
+                    KeyCode::Char('H') => {
+                        app.thread_filter.open();
+                        app.focused = Pane::Thread;
+                    }
+                    KeyCode::Char('T') => {
+                        app.time_filter.open();
+                        app.focused = Pane::TimeRange;
+                    }
+                    KeyCode::Char('n') if app.finding => app.find_next(true),
+                    KeyCode::Char('N') if app.finding => app.find_next(false),
+                    KeyCode::Char('f') => {
+                        app.find_text.clear();
+                        app.find_filter = false;
+                        app.finding = false;
+                        app.focused = Pane::Find;
+                    }
+                    KeyCode::Char('&') => app.start_local_filter(),
+                    KeyCode::Esc if !app.find_text.is_empty() || app.finding => app.clear_find(),
+                    KeyCode::Esc if app.pending => app.cancel_fetch(),
+                    KeyCode::Char('N') => {
+                        app.limit_filter.open();
+                        app.focused = Pane::Limit;
+                    }
+                    KeyCode::Char('R') => {
+                        // Filters never loaded if credentials had expired at startup
+                        let unloaded = app.profile_filter.items().is_empty();
+                        // Rebuilding the client reloads the credentials; keep it otherwise
+                        if app.credentials_expired || unloaded {
+                            app.reconnect();
                         }
-                        KeyCode::Char('M') => {
-                            app.search_mode_filter.open();
-                            app.focused = Pane::SearchMode;
+                        if unloaded {
+                            app.load_filters().await;
                         }
-                        KeyCode::Char('F') => {
-                            app.search_fields_filter.open();
-                            app.focused = Pane::SearchFields;
+                        app.refresh();
+                    }
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.rerun();
+                    }
+                    KeyCode::Char('r') => app.relative_time = !app.relative_time,
+                    KeyCode::Char('c') => app.dense = !app.dense,
+                    KeyCode::Char('e') => app.two_line = !app.two_line,
+                    KeyCode::Char('i') => app.show_trace_id = !app.show_trace_id,
+                    KeyCode::Char('t') => app.toggle_live_tail(),
+                    KeyCode::Char('w') => app.toggle_follow(),
+                    KeyCode::Char('0') => app.reset_filters(),
+                    KeyCode::Char('o') => app.cycle_sort(),
+                    KeyCode::Char('W') => app.focused = Pane::ConfirmDefaults,
+                    KeyCode::Down | KeyCode::Char('j') => app.scroll_down(),
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_up(),
+                    KeyCode::Right | KeyCode::Char('l') => {
+                        app.next_page();
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => {
+                        app.prev_page();
+                    }
+                    KeyCode::Enter if app.grouped && app.on_group_row() => app.toggle_selected_group(),
+                    KeyCode::Enter if !app.logs.is_empty() => match app.config.enter_action {
+                        EnterAction::ContextMenu => {
+                            app.context_cursor = 0;
+                            app.focused = Pane::LogContext;
                         }
-                        KeyCode::Char('E') => {
-                            if !app.logs.is_empty() {
-                                let content: String = app.logs.iter().map(|log| {
-                                    let mut line = format!("[{}] {} [{}] {}", log.timestamp, log.severity, log.logger, log.message);
-                                    if !log.stacktrace.is_empty() {
-                                        line.push('\n');
-                                        line.push_str(&log.stacktrace);
-                                    }
-                                    line
-                                }).collect::<Vec<_>>().join("\n");
-                                app.status = open_in_editor(terminal, &content, "log_explorer_page.log")?;
+                        EnterAction::DetailView => app.open_detail(),
+                        EnterAction::Copy if app.config.read_only => app.refuse_read_only(),
+                        EnterAction::Copy => {
+                            if let Some(log) = app.logs.get(app.log_index) {
+                                app.status = copy_to_clipboard(entry_text(log, app.config.export_max_length));
                             }
                         }
-                        _ => {}
                     },
-
-                    // --- Log context menu ---
-                    Pane::LogContext => match key.code {
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            app.context_cursor = (app.context_cursor + 1)
-                                .min(CONTEXT_MENU_OPTIONS.len() - 1);
+                    KeyCode::Char('g') if !app.logs.is_empty() => {
+                        app.goto_input.clear();
+                        app.focused = Pane::GotoPage;
+                    }
+                    KeyCode::Char('d') => app.open_detail(),
+                    KeyCode::Char('C') => app.focused = Pane::CountDistinct,
+                    KeyCode::Char('X') => app.exclude_search(),
+                    KeyCode::Char(']') if !app.grouped => app.jump_to_error(true),
+                    KeyCode::Char('[') if !app.grouped => app.jump_to_error(false),
+                    KeyCode::Char('U') => {
+                        app.status = match app.discover_url() {
+                            Some(url) => copy_to_clipboard(url),
+                            None => "Set dashboards_url in the config to copy Discover links".to_string(),
+                        };
+                    }
+                    KeyCode::Char('G') => app.toggle_grouped(),
+                    KeyCode::Char('m') => app.mute_selected_logger(),
+                    KeyCode::Char('a') => app.toggle_anchor(),
+                    KeyCode::Char(' ') => app.toggle_mark(),
+                    KeyCode::Char('b') => app.range_from_marks(),
+                    KeyCode::Char('z') => app.toggle_stacktrace(),
+                    KeyCode::Char('Z') => app.toggle_all_stacktraces(),
+                    KeyCode::Char('/') => {
+                        app.focused = Pane::Search;
+                    }
+                    KeyCode::Char('M') => {
+                        app.search_mode_filter.open();
+                        app.focused = Pane::SearchMode;
+                    }
+                    KeyCode::Char('F') => {
+                        app.search_fields_filter.open();
+                        app.focused = Pane::SearchFields;
+                    }
+                    KeyCode::Char('O') => app.start_save_page(),
+                    KeyCode::Char('E') if !app.logs.is_empty() => {
+                        let content: String = app.logs.iter()
+                            .map(|log| app.format_log_line(log))
+                            .collect::<Vec<_>>().join("\n");
+                        app.status = open_in_editor(terminal, &content, "log_explorer_page.log", app.config.editor_timeout_mins)
+                            .await?
+                            .status;
+                    }
+                    KeyCode::Char('D') => app.open_query_debug(),
+                    KeyCode::Char('?') => app.focused = Pane::Help,
+                    KeyCode::Char('Q') => {
+                        if let Some(name) = detached_editor() {
+                            app.status = format!(
+                                "{} returns before the query is edited; set $EDITOR to a blocking editor, e.g. code --wait",
+                                name
+                            );
+                        } else if let Some(body) = app.query_body() {
+                            let body = serde_json::to_string_pretty(&body)?;
+                            const FILENAME: &str = "log_explorer_query.json";
+                            let run = open_in_editor(terminal, &body, FILENAME, app.config.editor_timeout_mins).await?;
+                            app.status = run.status;
+                            // A cancelled or failed edit leaves the query as it was
+                            if run.completed {
+                                match std::fs::read_to_string(std::env::temp_dir().join(FILENAME)) {
+                                    Ok(edited) if edited != body => app.apply_raw_query(&edited),
+                                    Ok(_) => {}
+                                    Err(e) => app.status = format!("Failed to read the edited query: {}", e),
+                                }
+                            }
                         }
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            app.context_cursor = app.context_cursor.saturating_sub(1);
+                    }
+                    KeyCode::Char('y') => {
+                        if let Some(log) = app.logs.get(app.log_index) {
+                            app.status = copy_to_clipboard(app.format_log_line(log));
                         }
-                        KeyCode::Enter => {
-                            if let Some(log) = app.logs.get(app.log_index) {
-                                match app.context_cursor {
-                                    0 => {
-                                        let mut text = log.message.clone();
-                                        if !log.stacktrace.is_empty() {
-                                            text.push('\n');
-                                            text.push_str(&log.stacktrace);
-                                        }
-                                        match Clipboard::new().and_then(|mut cb| cb.set_text(text)) {
-                                            Ok(_) => app.status = "Copied to clipboard".to_string(),
-                                            Err(e) => app.status = format!("Clipboard error: {}", e),
-                                        }
+                    }
+                    KeyCode::Char('J') => {
+                        let json = serde_json::to_string(&app.filter_state())?;
+                        app.status = copy_to_clipboard(json);
+                    }
+                    _ => {}
+                },
+
+                // --- Help overlay: any key closes it ---
+                Pane::Help => app.focused = Pane::Logs,
+
+                // --- Generated query popup ---
+                Pane::QueryDebug => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.query_scroll = app.query_scroll.saturating_add(1);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.query_scroll = app.query_scroll.saturating_sub(1);
+                    }
+                    KeyCode::Esc | KeyCode::Char('D') | KeyCode::Char('q') => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Log context menu ---
+                Pane::LogContext => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.context_cursor = (app.context_cursor + 1)
+                            .min(CONTEXT_MENU_OPTIONS.len() - 1);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.context_cursor = app.context_cursor.saturating_sub(1);
+                    }
+                    KeyCode::Enter if app.config.read_only => {
+                        app.focused = Pane::Logs;
+                        app.refuse_read_only();
+                    }
+                    KeyCode::Enter if let Some(reason) = app.context_option_unavailable(app.context_cursor) => {
+                        app.focused = Pane::Logs;
+                        app.status = reason;
+                    }
+                    KeyCode::Enter => {
+                        app.focused = Pane::Logs;
+                        if let Some(log) = app.logs.get(app.log_index) {
+                            match app.context_cursor {
+                                0 => {
+                                    app.status = copy_to_clipboard(entry_text(log, app.config.export_max_length));
+                                }
+                                1 => {
+                                    // The editor gets the whole entry
+                                    let content = entry_text(log, 0);
+                                    app.status = open_in_editor(terminal, &content, "log_explorer_entry.log", app.config.editor_timeout_mins)
+                                        .await?
+                                        .status;
+                                }
+                                2 => app.start_save_entry(ExportFormat::Text),
+                                3 => app.start_save_entry(ExportFormat::Json),
+                                4 => {
+                                    if let Some(trace_id) = &log.trace_id {
+                                        app.status = copy_to_clipboard(trace_id.clone());
                                     }
-                                    1 => {
-                                        let mut content = log.message.clone();
-                                        if !log.stacktrace.is_empty() {
-                                            content.push('\n');
-                                            content.push_str(&log.stacktrace);
-                                        }
-                                        app.status = open_in_editor(terminal, &content, "log_explorer_entry.log")?;
+                                }
+                                5 => {
+                                    if let Some(url) = app.trace_url() {
+                                        app.status = open_url(&url).await;
                                     }
-                                    _ => {}
                                 }
+                                _ => {}
                             }
-                            app.focused = Pane::Logs;
                         }
-                        KeyCode::Esc => {
-                            app.focused = Pane::Logs;
-                        }
-                        _ => {}
-                    },
+                    }
+                    KeyCode::Esc => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
 
-                    // --- Search text input ---
-                    Pane::Search => match key.code {
-                        KeyCode::Char(c) => {
-                            app.search_text.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            app.search_text.pop();
-                        }
-                        KeyCode::Enter => {
-                            app.status = "Fetching logs...".to_string();
-                            terminal.draw(|f| ui::render(f, app))?;
-                            app.fetch_logs().await;
-                        }
-                        KeyCode::Esc => {
-                            app.focused = Pane::Logs;
-                        }
-                        _ => {}
-                    },
+                // --- Save entry path prompt ---
+                Pane::SaveEntry => match key.code {
+                    KeyCode::Tab => app.cycle_save_format(),
+                    KeyCode::Char(c) => app.save_path.push(c),
+                    KeyCode::Backspace => {
+                        app.save_path.pop();
+                    }
+                    KeyCode::Enter => {
+                        app.save_entry();
+                        app.focused = Pane::Logs;
+                    }
+                    KeyCode::Esc => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
 
-                    // --- Filter dropdown focused (typing mode) ---
-                    Pane::Profile | Pane::Application | Pane::Severity | Pane::TimeRange | Pane::Limit | Pane::SearchMode | Pane::SearchFields => match key.code {
-                        // Uppercase hotkeys always switch pane
-                        KeyCode::Char('P') => {
-                            app.profile_filter.open();
-                            app.focused = Pane::Profile;
-                        }
-                        KeyCode::Char('A') => {
-                            app.app_filter.open();
-                            app.focused = Pane::Application;
-                        }
-                        KeyCode::Char('S') => {
-                            app.severity_filter.open();
-                            app.focused = Pane::Severity;
-                        }
-                        KeyCode::Char('T') => {
-                            app.time_filter.open();
-                            app.focused = Pane::TimeRange;
-                        }
-                        KeyCode::Char('L') => app.focused = Pane::Logs,
-                        KeyCode::Char('/') => app.focused = Pane::Search,
-                        KeyCode::Char('M') => {
-                            app.search_mode_filter.open();
-                            app.focused = Pane::SearchMode;
-                        }
-                        KeyCode::Char('F') => {
-                            app.search_fields_filter.open();
-                            app.focused = Pane::SearchFields;
+                // --- Field to search by, picked from the detail view ---
+                Pane::PivotField => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        let last = app.pivot_fields().len().saturating_sub(1);
+                        app.pivot_cursor = (app.pivot_cursor + 1).min(last);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.pivot_cursor = app.pivot_cursor.saturating_sub(1);
+                    }
+                    KeyCode::Enter => app.pivot_search(),
+                    KeyCode::Esc => app.focused = Pane::Detail,
+                    _ => {}
+                },
+
+                // --- Distinct count prompt ---
+                Pane::CountDistinct => match key.code {
+                    KeyCode::Char(c) => app.distinct_field.push(c),
+                    KeyCode::Backspace => {
+                        app.distinct_field.pop();
+                    }
+                    KeyCode::Enter => {
+                        app.focused = Pane::Logs;
+                        app.count_distinct();
+                    }
+                    KeyCode::Esc => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Save defaults confirmation ---
+                Pane::ConfirmDefaults => match key.code {
+                    KeyCode::Char('y') | KeyCode::Enter => {
+                        app.save_defaults();
+                        app.focused = Pane::Logs;
+                    }
+                    KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {
+                        app.status = "Defaults unchanged".to_string();
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Selecting part of the message in the detail view ---
+                Pane::Detail if app.detail_selection.is_some() => match key.code {
+                    KeyCode::Left | KeyCode::Char('h') => app.move_selection_cursor(false, false),
+                    KeyCode::Right | KeyCode::Char('l') => app.move_selection_cursor(true, false),
+                    KeyCode::Char('b') => app.move_selection_cursor(false, true),
+                    KeyCode::Char('e') => app.move_selection_cursor(true, true),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        let width = terminal.size()?.width;
+                        app.scroll_detail_down(ui::detail_line_count(app, width));
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_detail_up(),
+                    KeyCode::Char('y') | KeyCode::Enter => {
+                        if let Some(text) = app.selected_text() {
+                            app.status = copy_to_clipboard(text);
                         }
+                        app.detail_selection = None;
+                    }
+                    KeyCode::Esc => app.detail_selection = None,
+                    _ => {}
+                },
 
-                        // Any other character -> filter input
-                        KeyCode::Char(c) => {
-                            app.active_filter_mut().type_char(c);
+                // --- Detail view ---
+                Pane::Detail => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        let width = terminal.size()?.width;
+                        app.scroll_detail_down(ui::detail_line_count(app, width));
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => app.scroll_detail_up(),
+                    KeyCode::Char('p') => {
+                        app.pivot_cursor = 0;
+                        app.focused = Pane::PivotField;
+                    }
+                    KeyCode::Char('s') if app.config.read_only => app.refuse_read_only(),
+                    KeyCode::Char('s') => app.start_detail_selection(),
+                    KeyCode::Char('v') | KeyCode::Char('x') | KeyCode::Char('w') => {
+                        match key.code {
+                            KeyCode::Char('v') => app.cycle_detail_mode(),
+                            KeyCode::Char('x') => app.detail_hex = !app.detail_hex,
+                            _ => app.detail_wrap = !app.detail_wrap,
                         }
-                        KeyCode::Backspace => {
-                            app.active_filter_mut().backspace();
+                        // Keep the scroll position if the new rendering is long enough
+                        let width = terminal.size()?.width;
+                        let last_line = ui::detail_line_count(app, width).saturating_sub(1);
+                        app.detail_scroll = app.detail_scroll.min(last_line as u16);
+                    }
+                    KeyCode::Esc | KeyCode::Char('d') | KeyCode::Char('q') => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Search text input ---
+                Pane::Search => match key.code {
+                    KeyCode::Char(c) => {
+                        app.search_text.push(c);
+                        app.search_edited();
+                    }
+                    KeyCode::Backspace => {
+                        app.search_text.pop();
+                        app.search_edited();
+                    }
+                    KeyCode::Up => app.recall_search(true),
+                    KeyCode::Down => app.recall_search(false),
+                    KeyCode::Enter => {
+                        app.record_search();
+                        app.fetch_logs();
+                        app.focused = Pane::Logs;
+                    }
+                    KeyCode::Esc => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Find within the page ---
+                Pane::Find => match key.code {
+                    KeyCode::Char(c) => {
+                        app.find_text.push(c);
+                        app.local_filter_edited();
+                    }
+                    KeyCode::Backspace => {
+                        app.find_text.pop();
+                        app.local_filter_edited();
+                    }
+                    KeyCode::Enter => {
+                        app.focused = Pane::Logs;
+                        if app.find_filter {
+                            app.local_filter_edited();
+                        } else if !app.find_text.is_empty() {
+                            app.start_find();
                         }
+                    }
+                    KeyCode::Esc => {
+                        app.find_text.clear();
+                        app.finding = false;
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Goto page prompt ---
+                Pane::GotoPage => match key.code {
+                    KeyCode::Char(c) if c.is_ascii_digit() => app.goto_input.push(c),
+                    KeyCode::Backspace => {
+                        app.goto_input.pop();
+                    }
+                    KeyCode::Enter => {
+                        app.focused = Pane::Logs;
+                        app.goto_page();
+                    }
+                    KeyCode::Esc => {
+                        app.focused = Pane::Logs;
+                    }
+                    _ => {}
+                },
+
+                // --- Filter dropdown focused (typing mode) ---
+                Pane::Profile | Pane::Application | Pane::Severity | Pane::Thread | Pane::TimeRange | Pane::Limit | Pane::SearchMode | Pane::SearchFields => match key.code {
+                    // Uppercase hotkeys always switch pane
+                    KeyCode::Char('P') => {
+                        app.profile_filter.open();
+                        app.focused = Pane::Profile;
+                    }
+                    KeyCode::Char('A') => {
+                        app.app_filter.open();
+                        app.focused = Pane::Application;
+                    }
+                    KeyCode::Char('S') => {
+                        app.severity_filter.open();
+                        app.focused = Pane::Severity;
+                    }
+                    KeyCode::Char('H') => {
+                        app.thread_filter.open();
+                        app.focused = Pane::Thread;
+                    }
+                    // Typed into the time dropdown, as in 2024-03-01T10:00
+                    KeyCode::Char('T') if app.focused != Pane::TimeRange => {
+                        app.time_filter.open();
+                        app.focused = Pane::TimeRange;
+                    }
+                    KeyCode::Char('L') => app.focused = Pane::Logs,
+                    KeyCode::Char('/') => app.focused = Pane::Search,
+                    KeyCode::Char('M') => {
+                        app.search_mode_filter.open();
+                        app.focused = Pane::SearchMode;
+                    }
+                    KeyCode::Char('F') => {
+                        app.search_fields_filter.open();
+                        app.focused = Pane::SearchFields;
+                    }
+
+                    KeyCode::Char(' ') if matches!(app.focused, Pane::Application | Pane::Severity) => {
+                        app.toggle_checked();
+                    }
+                    KeyCode::Char('!') if app.focused == Pane::Severity => {
+                        app.toggle_severity_exclude();
+                    }
 
-                        KeyCode::Down => app.active_filter_mut().next(),
-                        KeyCode::Up => app.active_filter_mut().previous(),
+                    // Any other character -> filter input
+                    KeyCode::Char(c) => {
+                        app.active_filter_mut().type_char(c);
+                    }
+                    KeyCode::Backspace => {
+                        app.active_filter_mut().backspace();
+                    }
 
-                        KeyCode::Enter => {
-                            let pane = app.focused;
+                    KeyCode::Tab if app.focused == Pane::Application && app.config.read_only => {
+                        app.refuse_read_only();
+                    }
+                    KeyCode::Tab if app.focused == Pane::Application => {
+                        app.toggle_favorite_app();
+                    }
+
+                    KeyCode::Down => app.active_filter_mut().next(),
+                    KeyCode::Up => app.active_filter_mut().previous(),
+                    KeyCode::Home => app.active_filter_mut().first(),
+                    KeyCode::End => app.active_filter_mut().last(),
+                    KeyCode::PageDown | KeyCode::PageUp => {
+                        let rows = ui::dropdown_page_size(terminal.size()?.height);
+                        if key.code == KeyCode::PageDown {
+                            app.active_filter_mut().page_down(rows);
+                        } else {
+                            app.active_filter_mut().page_up(rows);
+                        }
+                    }
+
+                    KeyCode::Enter
+                        if app.focused == Pane::TimeRange
+                            && let Some(error) = app.typed_time_range_error() =>
+                    {
+                        app.status = error;
+                    }
+                    KeyCode::Enter => {
+                        let pane = app.focused;
+                        if pane == Pane::TimeRange {
+                            app.confirm_time_filter();
+                        } else {
                             app.active_filter_mut().confirm();
-                            if pane == Pane::SearchMode || pane == Pane::SearchFields {
-                                app.focused = Pane::Logs;
-                            } else {
-                                app.status = "Fetching logs...".to_string();
-                                terminal.draw(|f| ui::render(f, app))?;
-                                app.fetch_logs().await;
-                            }
                         }
+                        match pane {
+                            Pane::TimeRange => app.time_overridden = true,
+                            Pane::Severity => app.apply_severity_time_range(),
+                            _ => {}
+                        }
+                        app.focused = Pane::Logs;
+                        if pane != Pane::SearchMode && pane != Pane::SearchFields {
+                            app.fetch_logs();
+                        }
+                    }
 
-                        KeyCode::Esc => app.focused = Pane::Logs,
-                        _ => {}
-                    },
-                }
+                    KeyCode::Esc => app.focused = Pane::Logs,
+                    _ => {}
+                },
             }
         }
     }
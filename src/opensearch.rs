@@ -1,21 +1,50 @@
-use anyhow::Result;
+use crate::config::{Auth, FieldMapping, NoiseFilter};
+use crate::search::{self, SearchTerm};
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use opensearch::auth::Credentials;
+use opensearch::cert::{Certificate, CertificateValidation};
 use opensearch::http::transport::{SingleNodeConnectionPool, TransportBuilder};
 use opensearch::{OpenSearch, SearchParts};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OnceCell;
 use url::Url;
 
 fn nullable_string<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
     Option::deserialize(d).map(|o: Option<String>| o.unwrap_or_default())
 }
 
+/// Read an empty string as missing.
+fn non_empty_string<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Option::deserialize(d).map(|o: Option<String>| o.filter(|s| !s.is_empty()))
+}
+
+/// Accept any scalar (e.g. a numeric syslog level) as a string.
+fn scalar_string<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    Value::deserialize(d).map(|v| scalar_to_string(&v).unwrap_or_default())
+}
+
+/// String form of a JSON string, number or bool.
+pub fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct LogEntry {
-    #[serde(rename = "@timestamp")]
+    /// Empty if the document has none, rather than dropping the entry.
+    #[serde(rename = "@timestamp", default, deserialize_with = "scalar_string")]
     pub timestamp: String,
     #[serde(default)]
     pub message: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "scalar_string")]
     pub severity: String,
     #[serde(default)]
     pub application: String,
@@ -27,10 +56,88 @@ pub struct LogEntry {
     pub profiles: String,
     #[serde(default)]
     pub method: String,
-    #[serde(default, rename = "traceId")]
+    #[serde(default, rename = "traceId", deserialize_with = "non_empty_string")]
     pub trace_id: Option<String>,
     #[serde(default, deserialize_with = "nullable_string")]
     pub stacktrace: String,
+    /// Top-level fields of the document not read into the fields above.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+    /// The raw `_source` document this entry was deserialized from.
+    #[serde(skip)]
+    pub source: Value,
+    /// Relevance of the hit to the search, if OpenSearch scored it.
+    #[serde(skip)]
+    pub score: Option<f64>,
+}
+
+impl LogEntry {
+    /// Read an entry from a `_source` document whose fields are named as in
+    /// `fields`, or `None` if it isn't a log entry.
+    pub fn from_source(source: Value, fields: &FieldMapping) -> Option<LogEntry> {
+        let mut doc = source.as_object()?.clone();
+        let mapped = [
+            ("@timestamp", &fields.timestamp),
+            ("message", &fields.message),
+            ("application", &fields.application),
+            ("logger", &fields.logger),
+            ("thread", &fields.thread),
+            ("profiles", &fields.profile),
+            ("stacktrace", &fields.stacktrace),
+            ("traceId", &fields.trace_id),
+        ];
+        for (key, path) in mapped {
+            match lookup(&source, path) {
+                Some(value) => doc.insert(key.to_string(), value.clone()),
+                None => doc.remove(key),
+            };
+        }
+        let mut entry: LogEntry = serde_json::from_value(Value::Object(doc)).ok()?;
+        // Mapped fields are shown under their usual names already
+        for (_, path) in mapped {
+            entry.extra.remove(path.as_str());
+        }
+        entry.source = source;
+        Some(entry)
+    }
+
+    /// The parsed `@timestamp`; one without an offset is taken as UTC.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(&self.timestamp).map(|time| time.with_timezone(&Utc))
+    }
+
+    /// Look up a dotted field path (e.g. `log.level`) in the raw document.
+    pub fn field(&self, path: &str) -> Option<&Value> {
+        lookup(&self.source, path)
+    }
+}
+
+/// A timestamp as OpenSearch returns it: RFC 3339 with a `Z` or offset and
+/// optional fractional seconds, a local date-time (with `T` or a space)
+/// taken as UTC, or milliseconds since the epoch. Keeps the offset so it can
+/// be shown as logged.
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some(time);
+    }
+    if !timestamp.is_empty() && timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        let millis = timestamp.parse().ok()?;
+        return DateTime::from_timestamp_millis(millis).map(|time| time.fixed_offset());
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok())
+        .map(|time| time.and_utc().fixed_offset())
+}
+
+/// The value at a dotted field path in `source`. A key that literally
+/// contains the dots takes precedence.
+fn lookup<'a>(source: &'a Value, path: &str) -> Option<&'a Value> {
+    if let Some(value) = source.get(path) {
+        return Some(value);
+    }
+    path.split('.')
+        .try_fold(source, |value, segment| value.get(segment))
 }
 
 #[derive(Debug)]
@@ -38,11 +145,80 @@ pub struct AvailableFilters {
     pub environments: Vec<String>,
     pub applications: Vec<String>,
     pub severities: Vec<String>,
+    pub threads: Vec<String>,
+    /// Set when the query had to fall back to a degraded form.
+    pub note: Option<&'static str>,
 }
 
-async fn create_client(endpoint_url: &str, aws_region: &str) -> Result<OpenSearch> {
-    let url = Url::parse(endpoint_url)?;
+/// Where and how to reach the cluster.
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    pub endpoint_url: String,
+    pub auth: Auth,
+    pub ca_cert_path: Option<String>,
+    /// Index names or patterns to search, e.g. `logs-*` or `logs-2024.06.01`.
+    pub indices: Vec<String>,
+}
+
+/// A client built on first use and shared by every request after that, as
+/// building one loads the AWS config. It is dropped with [`SharedClient::reset`]
+/// once its credentials have expired, so the next request loads them again.
+#[derive(Clone, Default)]
+pub struct SharedClient(Arc<Mutex<Arc<OnceCell<OpenSearch>>>>);
+
+impl SharedClient {
+    pub async fn get(&self, cluster: &Cluster) -> Result<OpenSearch> {
+        let cell = Arc::clone(&self.0.lock().unwrap_or_else(PoisonError::into_inner));
+        cell.get_or_try_init(|| create_client(cluster)).await.cloned()
+    }
+
+    /// Build a new client on the next request. Requests already running keep
+    /// the old one.
+    pub fn reset(&self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Arc::default();
+    }
+}
+
+async fn create_client(cluster: &Cluster) -> Result<OpenSearch> {
+    if cluster.indices.iter().all(|index| index.trim().is_empty()) {
+        anyhow::bail!("No indices configured");
+    }
+    // A port or path prefix (e.g. https://host:8443/opensearch) is kept:
+    // the connection adds a trailing slash and joins request paths onto it.
+    let url = Url::parse(&cluster.endpoint_url)
+        .with_context(|| format!("Invalid endpoint URL {}", cluster.endpoint_url))?;
     let conn_pool = SingleNodeConnectionPool::new(url);
+    let builder = TransportBuilder::new(conn_pool);
+    let mut builder = match &cluster.auth {
+        Auth::Aws { region } => {
+            let credentials = load_aws_config(region)
+                .await?
+                .try_into()
+                .map_err(|e| anyhow::anyhow!("Cannot use the AWS credentials: {}", e))?;
+            builder.auth(credentials)
+        }
+        Auth::Basic { username, password } => {
+            builder.auth(Credentials::Basic(username.clone(), password.clone()))
+        }
+        Auth::ApiKey { id, key } => {
+            if id.trim().is_empty() || key.trim().is_empty() {
+                anyhow::bail!("API key auth needs both an id and a key");
+            }
+            builder.auth(Credentials::ApiKey(id.trim().to_string(), key.trim().to_string()))
+        }
+        Auth::None => builder,
+    };
+    if let Some(path) = &cluster.ca_cert_path {
+        builder = builder.cert_validation(CertificateValidation::Full(load_ca_cert(path)?));
+    }
+    let transport = builder
+        .build()
+        .map_err(|e| anyhow::anyhow!("Cannot build the OpenSearch client: {}", e))?;
+    Ok(OpenSearch::new(transport))
+}
+
+/// The AWS config requests are signed with.
+async fn load_aws_config(region: &str) -> Result<aws_config::SdkConfig> {
     // Only load ~/.aws/credentials (skip ~/.aws/config which may contain
     // login_session directives that cause auth failures with the Rust SDK).
     #[allow(deprecated)]
@@ -50,136 +226,416 @@ async fn create_client(endpoint_url: &str, aws_region: &str) -> Result<OpenSearc
         .include_default_credentials_file(true)
         .include_default_config_file(false)
         .build();
-    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-        .region(aws_config::Region::new(aws_region.to_string()))
-        .profile_files(profile_files)
-        .load()
-        .await;
-    let transport = TransportBuilder::new(conn_pool)
-        .auth(aws_config.clone().try_into()?)
-        .build()?;
-    Ok(OpenSearch::new(transport))
+    let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .profile_files(profile_files);
+    // Without a configured region, AWS_REGION and the profile decide
+    let region = region.trim();
+    if !region.is_empty() {
+        loader = loader.region(aws_config::Region::new(region.to_string()));
+    }
+    let aws_config = loader.load().await;
+    if aws_config.region().is_none() {
+        anyhow::bail!("No AWS region configured or found in the environment");
+    }
+    Ok(aws_config)
 }
 
-pub async fn fetch_available_filters(endpoint_url: &str, aws_region: &str) -> Result<AvailableFilters> {
-    let client = create_client(endpoint_url, aws_region).await?;
-
-    let response = client
-        .search(SearchParts::Index(&["logs-*"]))
-        .body(json!({
-            "size": 0,
-            "query": {
-                "range": {"@timestamp": {"gte": "now-24h"}}
-            },
-            "aggs": {
-                "applications": {
-                    "terms": {
-                        "field": "application.keyword",
-                        "size": 100,
-                        "order": {"_key": "asc"}
-                    }
-                },
-                "profiles": {
-                    "terms": {
-                        "field": "profiles.keyword",
-                        "size": 20,
-                        "order": {"_key": "asc"}
-                    }
-                },
-                "severities": {
-                    "terms": {
-                        "field": "severity.keyword",
-                        "size": 20,
-                        "order": {"_key": "asc"}
-                    }
-                }
-            }
-        }))
-        .send()
-        .await?;
+/// Read a PEM root certificate to trust in addition to the system store.
+fn load_ca_cert(path: &str) -> Result<Certificate> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Cannot read CA certificate {}", path))?;
+    Certificate::from_pem(&pem)
+        .map_err(|e| anyhow::anyhow!("Cannot parse CA certificate {}: {}", path, e))
+}
 
-    let body: Value = response.json().await?;
+pub async fn fetch_available_filters(
+    client: &OpenSearch,
+    indices: &[String],
+    fields: &FieldMapping,
+    severity_field: &str,
+) -> Result<AvailableFilters> {
+    let body = |suffix| filters_body(suffix, fields, severity_field);
+    let (body, note) = match run_search(client, indices, body(".keyword")).await {
+        Err(e) if is_field_error(&e) => (
+            run_search(client, indices, body("")).await?,
+            Some("aggregating on base fields (no .keyword mapping)"),
+        ),
+        result => (result?, None),
+    };
 
     let environments = extract_bucket_keys(&body["aggregations"]["profiles"]);
     let applications = extract_bucket_keys(&body["aggregations"]["applications"]);
-    let severities = extract_bucket_keys(&body["aggregations"]["severities"]);
+    let mut severities = extract_bucket_keys(&body["aggregations"]["severities"]);
+    if severities.is_empty() {
+        // Numeric severities have no `.keyword` subfield, which silently
+        // yields no buckets; aggregate the field itself instead.
+        let body = json!({
+            "size": 0,
+            "query": last_day(fields),
+            "aggs": {"severities": terms_agg(severity_field.to_string(), 20)}
+        });
+        if let Ok(body) = run_search(client, indices, body).await {
+            severities = extract_bucket_keys(&body["aggregations"]["severities"]);
+        }
+    }
+    let threads = extract_bucket_keys(&body["aggregations"]["threads"]);
 
     Ok(AvailableFilters {
         environments,
         applications,
         severities,
+        threads,
+        note,
+    })
+}
+
+/// Aggregation body for the filter dropdowns. `suffix` is appended to each
+/// field name, normally `.keyword`.
+fn filters_body(suffix: &str, fields: &FieldMapping, severity_field: &str) -> Value {
+    let terms = |field: &str, size: u32| terms_agg(format!("{}{}", field, suffix), size);
+    json!({
+        "size": 0,
+        "query": last_day(fields),
+        "aggs": {
+            "applications": terms(&fields.application, 100),
+            "profiles": terms(&fields.profile, 20),
+            "severities": terms(severity_field, 20),
+            "threads": terms(&fields.thread, 100)
+        }
+    })
+}
+
+/// Clause matching the entries of the last 24 hours.
+fn last_day(fields: &FieldMapping) -> Value {
+    TimeRange::Since("now-24h".to_string()).clause(&fields.timestamp)
+}
+
+fn terms_agg(field: String, size: u32) -> Value {
+    json!({
+        "terms": {
+            "field": field,
+            "size": size,
+            "order": {"_key": "asc"}
+        }
     })
 }
 
+/// Run a search against the log indices, turning error responses into
+/// errors carrying the reason reported by OpenSearch.
+async fn run_search(client: &OpenSearch, indices: &[String], body: Value) -> Result<Value> {
+    let indices: Vec<&str> = indices
+        .iter()
+        .map(|index| index.trim())
+        .filter(|index| !index.is_empty())
+        .collect();
+    let response = client
+        .search(SearchParts::Index(&indices))
+        .body(body)
+        .send()
+        .await?;
+
+    let status = response.status_code();
+    let body: Value = response.json().await?;
+    if !status.is_success() {
+        let error = &body["error"];
+        let cause = error["root_cause"].get(0).unwrap_or(error);
+        // AWS rejects requests (e.g. with expired credentials) with a bare `message`
+        let reason = cause["reason"]
+            .as_str()
+            .or_else(|| error["reason"].as_str())
+            .or_else(|| body["message"].as_str())
+            .unwrap_or("unknown error");
+        return Err(SearchError {
+            status: status.as_u16(),
+            kind: cause["type"].as_str().unwrap_or_default().to_string(),
+            reason: reason.to_string(),
+        }
+        .into());
+    }
+    Ok(body)
+}
+
+/// An error response to a search, with the root cause OpenSearch reported.
+#[derive(Debug)]
+struct SearchError {
+    status: u16,
+    /// Exception type, e.g. `illegal_argument_exception`; empty if not given.
+    kind: String,
+    reason: String,
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OpenSearch returned {}: {}", self.status, self.reason)
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+/// Whether an error means the AWS credentials have expired, e.g. because
+/// the SSO session ran out. Matches the `ExpiredToken`/`ExpiredTokenException`
+/// error codes, the SSO token endpoint's `invalid_grant` and the 403 AWS
+/// answers a request signed with expired credentials with, not any message
+/// that happens to say "expired".
+pub fn is_expired_credentials(err: &anyhow::Error) -> bool {
+    if let Some(error) = err.downcast_ref::<SearchError>()
+        && error.status == 403
+        && error.reason.contains("security token included in the request is expired")
+    {
+        return true;
+    }
+    let msg = format!("{:#}", err);
+    ["ExpiredToken", "invalid_grant"].iter().any(|code| msg.contains(code))
+}
+
+/// Whether an error was caused by a field that is missing or mapped in a way
+/// that doesn't support sorting/aggregating.
+fn is_field_error(err: &anyhow::Error) -> bool {
+    let Some(error) = err.downcast_ref::<SearchError>() else {
+        return false;
+    };
+    if error.reason.contains("No mapping found for") {
+        return true;
+    }
+    error.kind == "illegal_argument_exception"
+        && [
+            "Fielddata is disabled",
+            "Text fields are not optimised",
+            "can't be used for sorting",
+            "is not aggregatable",
+        ]
+        .iter()
+        .any(|pattern| error.reason.contains(pattern))
+}
+
 fn extract_bucket_keys(agg: &Value) -> Vec<String> {
     agg["buckets"]
         .as_array()
         .map(|buckets| {
             buckets
                 .iter()
-                .filter_map(|b| b["key"].as_str().map(String::from))
+                .filter_map(|b| scalar_to_string(&b["key"]))
                 .collect()
         })
         .unwrap_or_default()
 }
 
+/// The window of the timestamp field to search, as date math expressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeRange {
+    /// From e.g. `now-5m` until now.
+    Since(String),
+    /// Between two points, e.g. `2024-06-01T14:32:00.000Z||-15m` and `…||+15m`.
+    Between { gte: String, lte: String },
+}
+
+impl TimeRange {
+    /// Start and end, as date math expressions.
+    pub fn bounds(&self) -> (&str, &str) {
+        match self {
+            TimeRange::Since(gte) => (gte, "now"),
+            TimeRange::Between { gte, lte } => (gte, lte),
+        }
+    }
+
+    fn clause(&self, timestamp_field: &str) -> Value {
+        match self {
+            TimeRange::Since(gte) => json!({"range": {timestamp_field: {"gte": gte}}}),
+            TimeRange::Between { gte, lte } => {
+                json!({"range": {timestamp_field: {"gte": gte, "lte": lte}}})
+            }
+        }
+    }
+}
+
+/// Order of the search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Newest first.
+    #[default]
+    Time,
+    /// Oldest first, for reading events in the order they happened.
+    Oldest,
+    /// Best match first, then newest.
+    Score,
+    /// Most severe first, then newest. Fetched newest first and reordered
+    /// within the page.
+    Severity,
+}
+
+impl SortOrder {
+    pub fn next(self) -> Self {
+        match self {
+            SortOrder::Time => SortOrder::Oldest,
+            SortOrder::Oldest => SortOrder::Score,
+            SortOrder::Score => SortOrder::Severity,
+            SortOrder::Severity => SortOrder::Time,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Time => "newest first",
+            SortOrder::Oldest => "oldest first",
+            SortOrder::Score => "best match first",
+            SortOrder::Severity => "most severe first (within the page)",
+        }
+    }
+
+    /// Compact form for the status bar.
+    pub fn short_label(self) -> &'static str {
+        match self {
+            SortOrder::Time => "↓ newest",
+            SortOrder::Oldest => "↑ oldest",
+            SortOrder::Score => "best match",
+            SortOrder::Severity => "most severe",
+        }
+    }
+
+    fn clauses(self, timestamp_field: &str) -> Value {
+        match self {
+            SortOrder::Time | SortOrder::Severity => json!([{timestamp_field: "desc"}]),
+            SortOrder::Oldest => json!([{timestamp_field: "asc"}]),
+            SortOrder::Score => json!(["_score", {timestamp_field: "desc"}]),
+        }
+    }
+}
+
+/// Parameters of a log search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogQuery {
+    pub profile: String,
+    /// Applications any of which an entry must come from; empty for all.
+    pub applications: Vec<String>,
+    /// Severities any of which an entry must have; empty for all.
+    pub severities: Vec<String>,
+    /// Severities an entry must not have.
+    pub exclude_severities: Vec<String>,
+    /// Document field holding the severity, e.g. `severity` or `log.level`.
+    pub severity_field: String,
+    /// Document fields of the other parts of an entry.
+    pub fields: FieldMapping,
+    pub thread: Option<String>,
+    pub time_range: TimeRange,
+    pub search: Option<String>,
+    /// Search text whose matches are left out.
+    pub exclude: Option<String>,
+    pub search_exact: bool,
+    /// Document field searched, or `None` to search all fields.
+    pub search_field: Option<String>,
+    /// A document field path and the value it must have, picked from an
+    /// entry; the path is used as is, not mapped like search terms.
+    pub pivot: Option<(String, String)>,
+    pub sort: SortOrder,
+    /// Entries to leave out.
+    pub noise: Vec<NoiseFilter>,
+    pub size: i64,
+    pub from: i64,
+    /// Search body written by hand, sent instead of one built from the
+    /// fields above except for `from`, which follows the page.
+    pub raw_body: Option<Value>,
+}
+
+#[derive(Clone)]
 pub struct LogResult {
     pub logs: Vec<LogEntry>,
     pub total: u64,
+    /// Hits that were returned but couldn't be read as log entries.
+    pub malformed: usize,
+    /// Set when the query had to fall back to a degraded form.
+    pub note: Option<&'static str>,
 }
 
-pub async fn fetch_logs(
-    endpoint_url: &str,
-    aws_region: &str,
-    application: Option<&str>,
-    profile: &str,
-    severity: Option<&str>,
-    time_range: &str,
-    search: Option<&str>,
-    search_exact: bool,
-    search_all_fields: bool,
-    size: i64,
-    from: i64,
-) -> Result<LogResult> {
-    let client = create_client(endpoint_url, aws_region).await?;
-
+/// The `bool` query selecting the entries matching `q`.
+fn query_clause(q: &LogQuery) -> Value {
+    let fields = &q.fields;
     let mut must = vec![
-        json!({"match": {"profiles": profile}}),
-        json!({"range": {"@timestamp": {"gte": time_range}}}),
+        json!({"match": {fields.profile.as_str(): q.profile}}),
+        q.time_range.clause(&fields.timestamp),
     ];
-    if let Some(app) = application {
-        must.push(json!({"match": {"application": app}}));
-    }
-    if let Some(sev) = severity {
-        must.push(json!({"match": {"severity": sev}}));
-    }
-    if let Some(q) = search {
-        if search_exact {
-            if search_all_fields {
-                must.push(json!({"multi_match": {"query": q, "type": "phrase", "fields": ["*"]}}));
-            } else {
-                must.push(json!({"match_phrase": {"message": q}}));
-            }
-        } else if search_all_fields {
-            must.push(json!({"query_string": {"query": format!("*{}*", q)}}));
-        } else {
-            must.push(json!({"query_string": {"default_field": "message", "query": format!("*{}*", q)}}));
+    must.extend(any_of(&fields.application, &q.applications));
+    must.extend(any_of(&q.severity_field, &q.severities));
+    if let Some(thread) = &q.thread {
+        must.push(json!({"match_phrase": {fields.thread.as_str(): thread}}));
+    }
+    if let Some((field, value)) = &q.pivot {
+        must.push(json!({"match_phrase": {field.as_str(): value}}));
+    }
+    if let Some(text) = &q.search {
+        must.extend(search_clauses(q, text));
+    }
+
+    let mut must_not: Vec<Value> = q
+        .noise
+        .iter()
+        .filter_map(|noise| noise_clause(noise, fields))
+        .collect();
+    must_not.extend(any_of(&q.severity_field, &q.exclude_severities));
+    if let Some(text) = &q.exclude {
+        must_not.push(json!({"bool": {"must": search_clauses(q, text)}}));
+    }
+
+    json!({ "bool": { "must": must, "must_not": must_not } })
+}
+
+/// Clause matching entries whose `field` matches any of `values`, or
+/// `None` if there are none.
+fn any_of(field: &str, values: &[String]) -> Option<Value> {
+    match values {
+        [] => None,
+        [value] => Some(json!({"match": {field: value}})),
+        values => {
+            let should: Vec<Value> = values.iter().map(|value| json!({"match": {field: value}})).collect();
+            Some(json!({"bool": {"should": should, "minimum_should_match": 1}}))
         }
     }
+}
 
-    let response = client
-        .search(SearchParts::Index(&["logs-*"]))
-        .body(json!({
-            "query": { "bool": { "must": must } },
-            "from": from,
-            "size": size,
-            "sort": [{"@timestamp": "desc"}],
-            "track_total_hits": true
-        }))
-        .send()
-        .await?;
+/// The search body for the page of entries `q` selects.
+pub fn search_body(q: &LogQuery) -> Value {
+    if let Some(raw) = &q.raw_body {
+        let mut body = raw.clone();
+        body["from"] = json!(q.from);
+        return body;
+    }
+    json!({
+        "query": query_clause(q),
+        "from": q.from,
+        "size": q.size,
+        "sort": q.sort.clauses(&q.fields.timestamp),
+        "track_scores": true,
+        "track_total_hits": true
+    })
+}
 
-    let body: Value = response.json().await?;
+/// The query of the raw body if there is one, otherwise the one built from `q`.
+fn effective_query(q: &LogQuery) -> Value {
+    match q.raw_body.as_ref().and_then(|body| body.get("query")) {
+        Some(query) => query.clone(),
+        None if q.raw_body.is_some() => json!({"match_all": {}}),
+        None => query_clause(q),
+    }
+}
+
+pub async fn fetch_logs(client: &OpenSearch, indices: &[String], q: &LogQuery) -> Result<LogResult> {
+    let query = query_clause(q);
+    let (body, note) = match run_search(client, indices, search_body(q)).await {
+        Err(e) if q.raw_body.is_none() && is_field_error(&e) => {
+            let unsorted = json!({
+                "query": query,
+                "from": q.from,
+                "size": q.size,
+                "track_scores": true,
+                "track_total_hits": true
+            });
+            (
+                run_search(client, indices, unsorted).await?,
+                Some("sort dropped: the timestamp field cannot be sorted on, entries are unordered"),
+            )
+        }
+        result => (result?, None),
+    };
 
     let total = body["hits"]["total"]["value"].as_u64().unwrap_or(0);
 
@@ -189,8 +645,228 @@ pub async fn fetch_logs(
 
     let logs: Vec<LogEntry> = hits
         .iter()
-        .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+        .filter_map(|hit| {
+            let mut entry = LogEntry::from_source(hit["_source"].clone(), &q.fields)?;
+            entry.score = hit["_score"].as_f64();
+            Some(entry)
+        })
         .collect();
+    let malformed = hits.len() - logs.len();
+
+    Ok(LogResult { logs, total, malformed, note })
+}
 
-    Ok(LogResult { logs, total })
+/// Entry counts of `q` per `interval` (e.g. `5m`) across its time range, as
+/// bucket start in epoch millis and count, oldest first.
+pub async fn fetch_histogram(
+    client: &OpenSearch,
+    indices: &[String],
+    q: &LogQuery,
+    interval: &str,
+) -> Result<Vec<(i64, u64)>> {
+    let (min, max) = q.time_range.bounds();
+    let body = json!({
+        "size": 0,
+        "query": effective_query(q),
+        "aggs": {
+            "volume": {
+                "date_histogram": {
+                    "field": q.fields.timestamp,
+                    "fixed_interval": interval,
+                    "min_doc_count": 0,
+                    "extended_bounds": {"min": min, "max": max}
+                }
+            }
+        }
+    });
+    let body = run_search(client, indices, body).await?;
+    let buckets = body["aggregations"]["volume"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|b| Some((b["key"].as_i64()?, b["doc_count"].as_u64()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(buckets)
+}
+
+/// Number of distinct values of `field` among the entries matching `q`.
+/// Approximate for large counts, as cardinality aggregations are.
+pub async fn count_distinct(
+    client: &OpenSearch,
+    indices: &[String],
+    q: &LogQuery,
+    field: &str,
+) -> Result<u64> {
+    let body = |field: String| {
+        json!({
+            "size": 0,
+            "query": effective_query(q),
+            "aggs": {"distinct": {"cardinality": {"field": field}}}
+        })
+    };
+    let distinct = |body: &Value| body["aggregations"]["distinct"]["value"].as_u64().unwrap_or(0);
+
+    // A missing `.keyword` subfield counts zero rather than failing, so fall
+    // back to the field itself unless the keyword count found something.
+    let keyword = run_search(client, indices, body(format!("{}.keyword", field))).await;
+    if let Ok(body) = &keyword
+        && distinct(body) > 0
+    {
+        return Ok(distinct(body));
+    }
+    match run_search(client, indices, body(field.to_string())).await {
+        Ok(body) => Ok(distinct(&body)),
+        Err(e) if is_field_error(&e) => keyword.map(|body| distinct(&body)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Clauses that all match for entries found by the search `text`.
+fn search_clauses(q: &LogQuery, text: &str) -> Vec<Value> {
+    if q.search_exact {
+        return vec![phrase_clause(q, text)];
+    }
+    search::parse_terms(text, |name| search_field(q, name).is_some())
+        .into_iter()
+        .map(|term| match term {
+            SearchTerm::Phrase(phrase) => phrase_clause(q, &phrase),
+            SearchTerm::Word(word) => wildcard_clause(&word, q.search_field.as_deref()),
+            SearchTerm::Field { field, value } => {
+                let field = search_field(q, &field).unwrap_or(&field);
+                json!({"match": {field: {"query": value, "operator": "and"}}})
+            }
+        })
+        .collect()
+}
+
+fn phrase_clause(q: &LogQuery, phrase: &str) -> Value {
+    match &q.search_field {
+        Some(field) => json!({"match_phrase": {field.as_str(): phrase}}),
+        None => json!({"multi_match": {"query": phrase, "type": "phrase", "fields": ["*"]}}),
+    }
+}
+
+/// The document field a `name:value` search term searches. The names of the
+/// parts of an entry (`logger`, `app`, `level`, …) refer to their configured
+/// fields, which can also be named directly; any other name isn't a field.
+fn search_field<'a>(q: &'a LogQuery, name: &str) -> Option<&'a str> {
+    let fields = &q.fields;
+    let field = match name {
+        "level" | "severity" => &q.severity_field,
+        "message" | "msg" => &fields.message,
+        "app" | "application" => &fields.application,
+        "logger" => &fields.logger,
+        "thread" => &fields.thread,
+        "trace" | "traceId" => &fields.trace_id,
+        name => [
+            &q.severity_field,
+            &fields.message,
+            &fields.application,
+            &fields.logger,
+            &fields.thread,
+            &fields.profile,
+            &fields.stacktrace,
+            &fields.trace_id,
+        ]
+        .into_iter()
+        .find(|field| *field == name)?,
+    };
+    Some(field.as_str())
+}
+
+/// Clause matching the entries of a noise filter, or `None` if it's empty.
+fn noise_clause(noise: &NoiseFilter, fields: &FieldMapping) -> Option<Value> {
+    let mut clauses = Vec::new();
+    if let Some(logger) = &noise.logger {
+        clauses.push(json!({"match_phrase": {fields.logger.as_str(): logger}}));
+    }
+    if let Some(message) = &noise.message {
+        clauses.push(wildcard_clause(message, Some(&fields.message)));
+    }
+    (!clauses.is_empty()).then(|| json!({"bool": {"must": clauses}}))
+}
+
+/// Clause matching `word` anywhere within `field`, or within any field.
+fn wildcard_clause(word: &str, field: Option<&str>) -> Value {
+    let query = format!("*{}*", escape_query_string(word));
+    match field {
+        Some(field) => json!({"query_string": {"default_field": field, "query": query}}),
+        None => json!({"query_string": {"query": query}}),
+    }
+}
+
+/// Escape characters that have a special meaning in `query_string` syntax,
+/// leaving the `*`/`?` wildcards usable.
+fn escape_query_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "+-=&|><!(){}[]^\"~:\\/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(timestamp: &str) -> String {
+        let time = parse_timestamp(timestamp).unwrap();
+        time.with_timezone(&Utc).to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    #[test]
+    fn parses_every_timestamp_form() {
+        assert_eq!(utc("2024-03-01T10:15:30Z"), "2024-03-01T10:15:30.000Z");
+        assert_eq!(utc("2024-03-01T12:15:30+02:00"), "2024-03-01T10:15:30.000Z");
+        assert_eq!(utc("2024-03-01T10:15:30.123456Z"), "2024-03-01T10:15:30.123Z");
+        assert_eq!(utc("2024-03-01T10:15:30.250"), "2024-03-01T10:15:30.250Z");
+        assert_eq!(utc("2024-03-01 10:15:30"), "2024-03-01T10:15:30.000Z");
+        assert_eq!(utc("2024-03-01 10:15:30.5"), "2024-03-01T10:15:30.500Z");
+        assert_eq!(utc("1709288130123"), "2024-03-01T10:15:30.123Z");
+    }
+
+    #[test]
+    fn keeps_the_logged_offset() {
+        let time = parse_timestamp("2024-03-01T12:15:30+02:00").unwrap();
+        assert_eq!(time.offset().local_minus_utc(), 2 * 3600);
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_timestamp() {
+        for timestamp in ["", "yesterday", "2024-03-01", "12:15:30", "-1709288130123"] {
+            assert!(parse_timestamp(timestamp).is_none(), "{}", timestamp);
+        }
+    }
+
+    #[test]
+    fn only_expired_token_codes_count_as_expired_credentials() {
+        let expired = [
+            anyhow::anyhow!("service error: ExpiredToken: The security token included in the request is expired"),
+            anyhow::anyhow!("ExpiredTokenException: The provided token has expired"),
+            anyhow::anyhow!("failed to refresh SSO token: invalid_grant"),
+            SearchError {
+                status: 403,
+                kind: String::new(),
+                reason: "The security token included in the request is expired".to_string(),
+            }
+            .into(),
+        ];
+        for error in expired {
+            assert!(is_expired_credentials(&error), "{}", error);
+        }
+        let other = [
+            "OpenSearch returned 400: search context expired",
+            "scroll id has expired",
+            "connection refused",
+        ];
+        for message in other {
+            assert!(!is_expired_credentials(&anyhow::anyhow!(message)), "{}", message);
+        }
+    }
 }
@@ -0,0 +1,152 @@
+/// A single term of the free-text search box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchTerm {
+    /// A bare word, matched as a substring.
+    Word(String),
+    /// A double-quoted run of text, matched as an exact phrase.
+    Phrase(String),
+    /// A `field:value` (or `field:"some value"`) pair, matched on that field.
+    Field { field: String, value: String },
+}
+
+/// Split search text into quoted phrases, `field:value` pairs and bare words.
+///
+/// `"connection reset" retry logger:Payment` yields the phrase
+/// `connection reset`, the word `retry` and the field term `logger` =
+/// `Payment`. Only names `is_field` accepts make a field term; anything else
+/// with a colon, like `14:32:05` or `ERROR:timeout`, stays a word. A field
+/// value may be quoted to include spaces. An unterminated quote runs to the
+/// end of the input, and empty quotes are ignored.
+pub fn parse_terms(text: &str, is_field: impl Fn(&str) -> bool) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            let phrase = phrase.trim();
+            if !phrase.is_empty() {
+                terms.push(SearchTerm::Phrase(phrase.to_string()));
+            }
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            let field = word.strip_suffix(':').filter(|field| is_field(field));
+            if let Some(field) = field
+                && chars.peek() == Some(&'"')
+            {
+                chars.next();
+                let value: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                if !value.trim().is_empty() {
+                    terms.push(SearchTerm::Field {
+                        field: field.to_string(),
+                        value: value.trim().to_string(),
+                    });
+                }
+            } else {
+                terms.push(field_term(&word, &is_field).unwrap_or(SearchTerm::Word(word)));
+            }
+        }
+    }
+
+    terms
+}
+
+/// A `field:value` word as a field term, if `field` is one `is_field` accepts.
+fn field_term(word: &str, is_field: impl Fn(&str) -> bool) -> Option<SearchTerm> {
+    let (field, value) = word.split_once(':')?;
+    // Leave things like URLs (`http://…`) to the message search
+    if !is_field(field) || value.is_empty() || value.starts_with('/') {
+        return None;
+    }
+    Some(SearchTerm::Field {
+        field: field.to_string(),
+        value: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(w: &str) -> SearchTerm {
+        SearchTerm::Word(w.to_string())
+    }
+
+    fn phrase(p: &str) -> SearchTerm {
+        SearchTerm::Phrase(p.to_string())
+    }
+
+    fn field(field: &str, value: &str) -> SearchTerm {
+        SearchTerm::Field { field: field.to_string(), value: value.to_string() }
+    }
+
+    fn known(name: &str) -> bool {
+        ["logger", "level", "app", "http.status"].contains(&name)
+    }
+
+    #[test]
+    fn mixed_quoted_and_unquoted_terms() {
+        assert_eq!(
+            parse_terms(r#""connection reset" retry  "by peer" now"#, known),
+            vec![phrase("connection reset"), word("retry"), phrase("by peer"), word("now")]
+        );
+    }
+
+    #[test]
+    fn quote_right_after_a_word_starts_a_phrase() {
+        assert_eq!(parse_terms(r#"retry"timed out""#, known), vec![word("retry"), phrase("timed out")]);
+    }
+
+    #[test]
+    fn unbalanced_quote_runs_to_the_end() {
+        assert_eq!(parse_terms(r#"error "socket closed"#, known), vec![word("error"), phrase("socket closed")]);
+    }
+
+    #[test]
+    fn empty_and_blank_phrases_are_dropped() {
+        assert_eq!(parse_terms(r#""" "   " timeout ""#, known), vec![word("timeout")]);
+    }
+
+    #[test]
+    fn several_field_terms() {
+        assert_eq!(
+            parse_terms("level:ERROR app:billing http.status:502 retry", known),
+            vec![field("level", "ERROR"), field("app", "billing"), field("http.status", "502"), word("retry")]
+        );
+    }
+
+    #[test]
+    fn quoted_field_values_keep_their_spaces() {
+        assert_eq!(
+            parse_terms(r#"logger:"com.acme Billing" level:"WARN""#, known),
+            vec![field("logger", "com.acme Billing"), field("level", "WARN")]
+        );
+    }
+
+    #[test]
+    fn unknown_prefixes_stay_words() {
+        assert_eq!(
+            parse_terms("14:32:05 ERROR:timeout logger:Payment", known),
+            vec![word("14:32:05"), word("ERROR:timeout"), field("logger", "Payment")]
+        );
+        assert_eq!(parse_terms(r#"host:"web 1""#, known), vec![word("host:"), phrase("web 1")]);
+    }
+
+    #[test]
+    fn urls_and_empty_values_stay_words() {
+        assert_eq!(
+            parse_terms("logger:/var/log logger: app:", known),
+            vec![word("logger:/var/log"), word("logger:"), word("app:")]
+        );
+    }
+}
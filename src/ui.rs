@@ -1,10 +1,17 @@
-use crate::app::{App, Pane, CONTEXT_MENU_OPTIONS};
-use crate::filter_field::FilterField;
+use crate::app::{
+    severity_rank, App, GroupRow, Pane, TextSelection, CONTEXT_MENU_OPTIONS, FLASH_DURATION,
+};
+use crate::config::{AppConfig, DetailMode, DisplayTimezone};
+use crate::filter_field::{self, FilterField};
+use crate::opensearch::{self, parse_timestamp, LogEntry};
 use ratatui::prelude::*;
 use ratatui::widgets::{
     Block, Borders, Cell, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph, Row,
-    Table, TableState,
+    Sparkline, Table, TableState, Wrap,
 };
+use chrono::{TimeDelta, Utc};
+use serde_json::Value;
+use std::borrow::Cow;
 
 pub fn render(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
@@ -17,57 +24,128 @@ pub fn render(f: &mut Frame, app: &App) {
         .split(f.area());
 
     render_filter_bar(f, chunks[0], app);
-    render_logs_table(f, chunks[1], app);
+    let table_area = if app.config.show_histogram {
+        let [histogram_area, table_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Min(3)]).areas(chunks[1]);
+        render_histogram(f, histogram_area, app);
+        table_area
+    } else {
+        chunks[1]
+    };
+    render_logs_table(f, table_area, app);
     render_status_bar(f, chunks[2], app);
 
     // Render dropdown popup if a filter pane is focused
     match app.focused {
         Pane::Profile => {
-            render_dropdown(f, chunks[0], chunks[1], 0, &app.profile_filter);
+            render_dropdown(f, chunks[0], chunks[1], 0, &app.profile_filter, |name| name.to_string());
         }
         Pane::Application => {
-            render_dropdown(f, chunks[0], chunks[1], 1, &app.app_filter);
+            let display = |name: &str| {
+                let mark = if app.app_filter.is_checked(name) { "✓ " } else { "" };
+                format!("{}{}", mark, app.config.app_display(name))
+            };
+            render_dropdown(f, chunks[0], chunks[1], 1, &app.app_filter, display);
         }
         Pane::Severity => {
-            render_dropdown(f, chunks[0], chunks[1], 2, &app.severity_filter);
+            let display = |name: &str| {
+                let mark = match app.severity_filter.is_checked(name) {
+                    true if app.severity_exclude => "✗ ",
+                    true => "✓ ",
+                    false => "",
+                };
+                format!("{}{}", mark, name)
+            };
+            render_dropdown(f, chunks[0], chunks[1], 2, &app.severity_filter, display);
+        }
+        Pane::Thread => {
+            render_dropdown(f, chunks[0], chunks[1], 3, &app.thread_filter, |name| name.to_string());
         }
         Pane::TimeRange => {
-            render_dropdown(f, chunks[0], chunks[1], 3, &app.time_filter);
+            render_dropdown(f, chunks[0], chunks[1], 4, &app.time_filter, |name| name.to_string());
         }
         Pane::Limit => {
-            render_dropdown(f, chunks[0], chunks[1], 4, &app.limit_filter);
+            render_dropdown(f, chunks[0], chunks[1], 5, &app.limit_filter, |name| name.to_string());
         }
         Pane::SearchMode => {
-            render_dropdown(f, chunks[0], chunks[1], 6, &app.search_mode_filter);
+            let field = &app.search_mode_filter;
+            render_dropdown(f, chunks[0], chunks[1], 7, field, |name| name.to_string());
         }
         Pane::SearchFields => {
-            render_dropdown(f, chunks[0], chunks[1], 7, &app.search_fields_filter);
+            let field = &app.search_fields_filter;
+            render_dropdown(f, chunks[0], chunks[1], 8, field, |name| name.to_string());
         }
-        Pane::Search | Pane::Logs => {}
+        Pane::Search | Pane::Find | Pane::Logs => {}
         Pane::LogContext => {
             render_log_context_menu(f, chunks[1], app);
         }
+        Pane::Detail => {
+            render_detail(f, chunks[1], app);
+        }
+        Pane::GotoPage => {
+            render_goto_page(f, chunks[1], app);
+        }
+        Pane::SaveEntry => {
+            render_save_entry(f, chunks[1], app);
+        }
+        Pane::CountDistinct => {
+            render_count_distinct(f, chunks[1], app);
+        }
+        Pane::PivotField => {
+            render_detail(f, chunks[1], app);
+            render_pivot_fields(f, chunks[1], app);
+        }
+        Pane::ConfirmDefaults => {
+            render_confirm(f, chunks[1], "Save the current view as the startup default?");
+        }
+        Pane::QueryDebug => {
+            render_query_debug(f, chunks[1], app);
+        }
+        Pane::Help => {
+            render_help(f, chunks[1]);
+        }
     }
 }
 
 // --- Filter bar (collapsed) ---
 
-const FILTER_CONSTRAINTS: [Constraint; 8] = [
-    Constraint::Length(25),
-    Constraint::Length(30),
-    Constraint::Length(18),
-    Constraint::Length(20),
-    Constraint::Length(16),
-    Constraint::Fill(1),
-    Constraint::Length(18),
-    Constraint::Length(18),
+/// Base widths of the filter chips; `None` marks the search box.
+const FILTER_WIDTHS: [Option<u16>; 9] = [
+    Some(25),
+    Some(30),
+    Some(18),
+    Some(20),
+    Some(20),
+    Some(16),
+    None,
+    Some(18),
+    Some(18),
 ];
 
-fn render_filter_bar(f: &mut Frame, area: Rect, app: &App) {
-    let panes = Layout::default()
+/// Widest the search box gets before the chips grow instead.
+const MAX_SEARCH_WIDTH: u16 = 60;
+
+/// Split the filter bar into chips. The search box takes the slack up to
+/// `MAX_SEARCH_WIDTH`; beyond that the chips grow by up to half their width
+/// and whatever is left is split evenly on both sides.
+fn filter_panes(area: Rect) -> std::rc::Rc<[Rect]> {
+    let chips: u16 = FILTER_WIDTHS.iter().flatten().sum();
+    let surplus = area.width.saturating_sub(chips + MAX_SEARCH_WIDTH);
+    let growth = surplus.min(chips / 2);
+    let constraints = FILTER_WIDTHS.map(|width| match width {
+        Some(width) => Constraint::Length(width + width * growth / chips),
+        None if surplus > 0 => Constraint::Length(MAX_SEARCH_WIDTH),
+        None => Constraint::Fill(1),
+    });
+    Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(FILTER_CONSTRAINTS)
-        .split(area);
+        .constraints(constraints)
+        .flex(layout::Flex::Center)
+        .split(area)
+}
+
+fn render_filter_bar(f: &mut Frame, area: Rect, app: &App) {
+    let panes = filter_panes(area);
 
     render_filter_chip(
         f,
@@ -83,7 +161,11 @@ fn render_filter_bar(f: &mut Frame, area: Rect, app: &App) {
         "Application",
         'A',
         app.focused == Pane::Application,
-        app.app_filter.selected_value().unwrap_or("—"),
+        &match app.app_filter.checked_values().as_slice() {
+            [] => app.config.app_display(app.app_filter.selected_value().unwrap_or("—")),
+            [single] => app.config.app_display(single),
+            checked => format!("{} selected", checked.len()).into(),
+        },
     );
     render_filter_chip(
         f,
@@ -91,28 +173,48 @@ fn render_filter_bar(f: &mut Frame, area: Rect, app: &App) {
         "Severity",
         'S',
         app.focused == Pane::Severity,
-        app.severity_filter.selected_value().unwrap_or("—"),
+        &severity_chip(app),
     );
     render_filter_chip(
         f,
         panes[3],
+        "Thread",
+        'H',
+        app.focused == Pane::Thread,
+        app.thread_filter.selected_value().unwrap_or("—"),
+    );
+    render_filter_chip(
+        f,
+        panes[4],
         "Time Range",
         'T',
         app.focused == Pane::TimeRange,
-        app.time_filter.selected_value().unwrap_or("—"),
+        &match app.anchor {
+            _ if let Some((from, to)) = app.custom_range => {
+                // Spell out the dates of a range that isn't within a day
+                let format = if from.date_naive() == to.date_naive() { "%H:%M:%S" } else { "%m-%d %H:%M" };
+                format!("{}–{}", from.format(format), to.format(format))
+            }
+            Some(anchor) => format!(
+                "{} @ {}",
+                app.time_filter.selected_value().unwrap_or("—"),
+                anchor.format("%H:%M:%S")
+            ),
+            None => app.time_filter.selected_value().unwrap_or("—").to_string(),
+        },
     );
     render_filter_chip(
         f,
-        panes[4],
+        panes[5],
         "Limit",
         'N',
         app.focused == Pane::Limit,
         app.limit_filter.selected_value().unwrap_or("—"),
     );
-    render_search_chip(f, panes[5], app);
+    render_search_chip(f, panes[6], app);
     render_filter_chip(
         f,
-        panes[6],
+        panes[7],
         "Mode",
         'M',
         app.focused == Pane::SearchMode,
@@ -120,7 +222,7 @@ fn render_filter_bar(f: &mut Frame, area: Rect, app: &App) {
     );
     render_filter_chip(
         f,
-        panes[7],
+        panes[8],
         "Fields",
         'F',
         app.focused == Pane::SearchFields,
@@ -128,6 +230,21 @@ fn render_filter_bar(f: &mut Frame, area: Rect, app: &App) {
     );
 }
 
+/// The severity chip's value, starting with NOT when the selected
+/// severities are left out.
+fn severity_chip(app: &App) -> String {
+    let value = match app.severity_filter.checked_values().as_slice() {
+        [] => app.severity_filter.selected_value().unwrap_or("—").to_string(),
+        [single] => single.to_string(),
+        checked => format!("{} selected", checked.len()),
+    };
+    if app.severity_exclude && !app.selected_severities().is_empty() {
+        format!("NOT {}", value)
+    } else {
+        value
+    }
+}
+
 fn render_filter_chip(
     f: &mut Frame,
     area: Rect,
@@ -140,7 +257,7 @@ fn render_filter_chip(
         .borders(Borders::ALL)
         .border_style(border_style(focused))
         .title(pane_title(name, hotkey, focused));
-    let widget = Paragraph::new(format!(" {}", value)).block(block);
+    let widget = Paragraph::new(format!(" {}", sanitize(value))).block(block);
     f.render_widget(widget, area);
 }
 
@@ -151,15 +268,35 @@ fn render_search_chip(f: &mut Frame, area: Rect, app: &App) {
         .border_style(border_style(focused))
         .title(pane_title("Search", '/', focused));
 
-    let content = if focused {
+    let content = if app.focused == Pane::Find {
+        Line::from(vec![
+            Span::styled(
+                if app.find_filter { " filter page: " } else { " find: " },
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(app.find_text.clone()),
+            Span::styled("█", Style::default().fg(Color::Cyan)),
+        ])
+    } else if focused {
         Line::from(vec![
             Span::raw(format!(" {}", app.search_text)),
             Span::styled("█", Style::default().fg(Color::Cyan)),
         ])
-    } else if app.search_text.is_empty() {
+    } else if app.search_text.is_empty() && app.exclude_text.is_empty() && app.pivot.is_none() {
         Line::from(Span::styled(" —", Style::default().fg(Color::DarkGray)))
     } else {
-        Line::from(format!(" {}", app.search_text))
+        let mut spans = Vec::new();
+        if let Some((field, value)) = &app.pivot {
+            spans.push(Span::styled(format!(" {} = {}", field, value), Style::default().fg(Color::Cyan)));
+        }
+        spans.push(Span::raw(format!(" {}", app.search_text)));
+        if !app.exclude_text.is_empty() {
+            spans.push(Span::styled(
+                format!(" NOT {}", app.exclude_text),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        Line::from(spans)
     };
 
     f.render_widget(Paragraph::new(content).block(block), area);
@@ -167,28 +304,30 @@ fn render_search_chip(f: &mut Frame, area: Rect, app: &App) {
 
 // --- Filter dropdown popup ---
 
+/// How many items an open dropdown shows at most in a terminal of the
+/// given height, i.e. how far PageUp/PageDown move.
+pub fn dropdown_page_size(terminal_height: u16) -> usize {
+    // Filter and status bars (3 each), the row kept below the popup, its
+    // borders (2) and the search row (1)
+    terminal_height.saturating_sub(6 + 1 + 3).max(1) as usize
+}
+
 fn render_dropdown(
     f: &mut Frame,
     filter_area: Rect,
     logs_area: Rect,
     pane_index: u16,
     field: &FilterField,
+    display: impl Fn(&str) -> String,
 ) {
     let filtered = field.filtered_items();
-    if filtered.is_empty() && field.filter_text().is_empty() {
-        return;
-    }
-
-    let filter_panes = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(FILTER_CONSTRAINTS)
-        .split(filter_area);
 
-    let anchor = filter_panes[pane_index as usize];
+    let anchor = filter_panes(filter_area)[pane_index as usize];
     let width = anchor.width.max(20);
     let max_height = logs_area.height.saturating_sub(1);
-    // +3 = borders (2) + search input row (1)
-    let height = (filtered.len() as u16 + 3).min(max_height).max(4);
+    // +3 = borders (2) + search input row (1), +1 for the pinned separator
+    let separator = field.pinned_boundary().is_some() as u16;
+    let height = (filtered.len().max(1) as u16 + 3 + separator).min(max_height).max(4);
 
     // Clamp so popup doesn't extend past the right edge of the screen
     let right_edge = logs_area.x + logs_area.width;
@@ -219,11 +358,33 @@ fn render_dropdown(
     ]);
     f.render_widget(Paragraph::new(search_line), inner[0]);
 
-    // Filtered items list
-    let list_items: Vec<ListItem> = filtered
+    if filtered.is_empty() {
+        // Keep the popup open so it's clear the text just matched nothing
+        let placeholder = if field.filter_text().is_empty() { "  No values" } else { "  No matches" };
+        let style = Style::default().fg(Color::DarkGray).italic();
+        f.render_widget(Paragraph::new(Span::styled(placeholder, style)), inner[1]);
+        return;
+    }
+
+    // Filtered items list, with a separator below any pinned items
+    let mut list_items: Vec<ListItem> = filtered
         .iter()
-        .map(|&i| ListItem::new(i))
+        .map(|&i| {
+            let text = sanitize(&display(i)).into_owned();
+            match filter_field::match_positions(&text, field.filter_text()) {
+                Some(positions) => ListItem::new(highlight_chars(&text, &positions)),
+                None => ListItem::new(text),
+            }
+        })
         .collect();
+    let mut selected = field.cursor();
+    if let Some(boundary) = field.pinned_boundary() {
+        let rule = "─".repeat(inner[1].width as usize);
+        list_items.insert(boundary, ListItem::new(rule).style(Style::default().fg(Color::DarkGray)));
+        if selected >= boundary {
+            selected += 1;
+        }
+    }
     let list = List::new(list_items)
         .highlight_style(
             Style::default()
@@ -234,74 +395,415 @@ fn render_dropdown(
         .highlight_symbol("▶ ")
         .highlight_spacing(HighlightSpacing::Always);
 
-    let mut state = ListState::default().with_selected(Some(field.cursor()));
+    let mut state = ListState::default().with_selected(Some(selected));
     f.render_stateful_widget(list, inner[1], &mut state);
 }
 
 // --- Logs table ---
 
 fn render_logs_table(f: &mut Frame, area: Rect, app: &App) {
+    if app.grouped {
+        return render_grouped_table(f, area, app);
+    }
+    let [area, minimap_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+    render_severity_minimap(f, minimap_area, app);
     let logs_focused = app.focused == Pane::Logs;
 
-    let header = Row::new(vec![
-        Cell::from("Timestamp").style(Style::default().bold()),
-        Cell::from("Level").style(Style::default().bold()),
-        Cell::from("Logger").style(Style::default().bold()),
-        Cell::from("Message").style(Style::default().bold()),
-        Cell::from("ST").style(Style::default().bold()),
-    ])
+    let (columns, hidden) = visible_columns(app, area.width);
+
+    let header = Row::new(
+        columns
+            .iter()
+            .map(|column| Cell::from(column.header(app)).style(Style::default().bold())),
+    )
     .height(1)
-    .bottom_margin(1);
+    .bottom_margin(if app.dense { 0 } else { 1 });
 
-    let rows: Vec<Row> = app
+    // The message column gets whatever the fixed-width columns leave over
+    let message_width =
+        rows_width(app, area.width).saturating_sub(columns_width(app, &columns)) + MIN_MESSAGE_WIDTH;
+
+    let mut rows: Vec<Row> = app
         .logs
         .iter()
-        .map(|log| {
-            let severity_style = match log.severity.as_str() {
-                "ERROR" => Style::default().fg(Color::Red).bold(),
-                "WARN" => Style::default().fg(Color::Yellow),
-                "INFO" => Style::default().fg(Color::Green),
-                "DEBUG" => Style::default().fg(Color::Blue),
-                _ => Style::default(),
+        .enumerate()
+        .filter(|(_, log)| app.row_visible(log))
+        .map(|(index, log)| {
+            let severity_style = severity_style(&log.severity);
+
+            let short_logger = app.config.logger_display(&log.logger);
+
+            let time = display_time(app, log);
+
+            // A find within the page takes over the highlighting from the search
+            let highlighted = if app.find_text.is_empty() { &app.search_text } else { &app.find_text };
+            let text = sanitize(&log.message);
+            let mut message = if app.two_line {
+                wrap_chars(&text, message_width as usize, 2)
+                    .iter()
+                    .map(|line| highlight_matches(line, highlighted))
+                    .collect()
+            } else {
+                Text::from(highlight_matches(&text, highlighted))
+            };
+            let expanded = !log.stacktrace.is_empty() && app.stacktrace_expanded(index);
+            if expanded {
+                message.extend(stacktrace_lines(&log.stacktrace));
+            }
+            let height = message.lines.len() as u16;
+
+            let stacktrace_mark = match (log.stacktrace.is_empty(), expanded) {
+                (true, _) => "",
+                (false, false) => "✘",
+                (false, true) => "▼",
             };
 
-            let short_logger = log.logger.rsplit('.').next().unwrap_or(&log.logger);
-
-            let time = log
-                .timestamp
-                .find('T')
-                .and_then(|t_pos| {
-                    let after_t = &log.timestamp[t_pos + 1..];
-                    let end = after_t
-                        .find('+')
-                        .or_else(|| after_t.rfind('-'))
-                        .unwrap_or(after_t.len());
-                    Some(after_t[..end.min(12)].to_string())
+            let mut message = Some(message);
+            let cells: Vec<Cell> = columns
+                .iter()
+                .map(|column| match *column {
+                    Column::Timestamp if index < app.followed_new => {
+                        Cell::from(sanitize(&time).into_owned())
+                            .style(Style::default().fg(Color::Green).bold())
+                    }
+                    Column::Timestamp => Cell::from(sanitize(&time).into_owned()),
+                    Column::Level => {
+                        Cell::from(sanitize(&log.severity).into_owned()).style(severity_style)
+                    }
+                    Column::Logger => Cell::from(sanitize(short_logger).into_owned()),
+                    Column::Message => Cell::from(message.take().unwrap_or_default()),
+                    Column::Custom(i) => Cell::from(
+                        sanitize(&field_text(log, &app.config.columns[i].path)).into_owned(),
+                    ),
+                    Column::TraceId => Cell::from(
+                        sanitize(log.trace_id.as_deref().unwrap_or_default()).into_owned(),
+                    )
+                    .style(Style::default().fg(Color::DarkGray)),
+                    Column::Stacktrace => {
+                        Cell::from(stacktrace_mark).style(Style::default().fg(Color::Red))
+                    }
+                    Column::Score => {
+                        let score = log.score.map(|s| format!("{:.2}", s)).unwrap_or_default();
+                        Cell::from(score).style(Style::default().fg(Color::Cyan))
+                    }
                 })
-                .unwrap_or_else(|| log.timestamp.clone());
+                .collect();
 
-            let message_cell = Cell::from(highlight_matches(&log.message, &app.search_text));
+            let style = if app.marked.contains(&index) {
+                Style::default().bg(Color::Blue)
+            } else {
+                flash_style(app, index)
+            };
+            Row::new(cells).height(height).style(style)
+        })
+        .collect();
 
-            let stacktrace_mark = if log.stacktrace.is_empty() { "" } else { "✘" };
+    // Rows hidden by the page filter don't count towards the selected row
+    let mut selected = app.logs[..app.log_index.min(app.logs.len())]
+        .iter()
+        .filter(|log| app.row_visible(log))
+        .count();
+    // Mark where the entries live tail brought in since the last key press end
+    let marker = app.unseen_entries;
+    if app.live_tail && !app.filtering_page() && marker > 0 && marker < rows.len() {
+        let separator = columns.iter().map(|column| match column {
+            Column::Message => Cell::from("── new ──"),
+            _ => Cell::from("─".repeat(column.min_width(app) as usize)),
+        });
+        rows.insert(marker, Row::new(separator).style(Style::default().fg(Color::Yellow)));
+        if selected >= marker {
+            selected += 1;
+        }
+    }
 
-            Row::new(vec![
-                Cell::from(time),
-                Cell::from(log.severity.clone()).style(severity_style),
-                Cell::from(short_logger.to_string()),
-                message_cell,
-                Cell::from(stacktrace_mark).style(Style::default().fg(Color::Red)),
-            ])
+    let borders = if app.dense { Borders::TOP | Borders::BOTTOM } else { Borders::ALL };
+    let mut block = Block::default()
+        .borders(borders)
+        .border_style(border_style(logs_focused))
+        .title(pane_title("Logs", 'L', logs_focused))
+        .title_top(live_tail_title(app));
+    if app.filtering_page() {
+        let note = format!(" {} of {} rows match \"{}\" ", rows.len(), app.logs.len(), app.find_text);
+        block = block.title_bottom(Span::styled(note, Style::default().fg(Color::Yellow)));
+    }
+    if hidden > 0 {
+        let note = format!(" {} column{} hidden ", hidden, if hidden == 1 { "" } else { "s" });
+        block = block.title_bottom(
+            Line::from(Span::styled(note, Style::default().fg(Color::DarkGray))).right_aligned(),
+        );
+    }
+    let table = Table::new(rows, columns.iter().map(|column| column.width(app)))
+    .header(header)
+    .block(block)
+    .row_highlight_style(Style::default().bg(Color::DarkGray))
+    .highlight_symbol("▶ ");
+
+    let mut state = TableState::default().with_selected(Some(selected));
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+/// The log volume across the time range, stretched to the full width, with
+/// the busiest bucket's count beside it.
+fn render_histogram(f: &mut Frame, area: Rect, app: &App) {
+    let counts: Vec<u64> = app.histogram.iter().map(|&(_, count)| count).collect();
+    let peak = counts.iter().copied().max().unwrap_or(0);
+    let label = if peak == 0 {
+        " no entries ".to_string()
+    } else {
+        format!(" peak {}/{} ", peak, app.histogram_interval)
+    };
+    let [chart, label_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(label.chars().count() as u16)])
+            .areas(area);
+    if peak > 0 {
+        let width = chart.width as usize;
+        let data: Vec<u64> = (0..width).map(|x| counts[x * counts.len() / width]).collect();
+        let sparkline = Sparkline::default()
+            .data(&data)
+            .max(peak)
+            .style(Style::default().fg(Color::Cyan));
+        f.render_widget(sparkline, chart);
+    }
+    f.render_widget(
+        Paragraph::new(label).style(Style::default().fg(Color::DarkGray)),
+        label_area,
+    );
+}
+
+/// A strip beside the table with one cell per slice of the page, colored by
+/// the slice's most severe entry. The selected entry's slice is marked.
+fn render_severity_minimap(f: &mut Frame, area: Rect, app: &App) {
+    // Level with the table's rows, inside its borders
+    let area = Rect {
+        y: area.y + 1,
+        height: area.height.saturating_sub(2),
+        ..area
+    };
+    let count = app.logs.len();
+    let rows = (area.height as usize).min(count);
+    let lines: Vec<Line> = (0..rows)
+        .map(|row| {
+            let start = row * count / rows;
+            let end = ((row + 1) * count / rows).max(start + 1);
+            let worst = app.logs[start..end]
+                .iter()
+                .min_by_key(|log| severity_rank(&log.severity))
+                .map_or("", |log| log.severity.as_str());
+            let color = severity_style(worst).fg.unwrap_or(Color::DarkGray);
+            let symbol = if (start..end).contains(&app.log_index) { "◆" } else { "█" };
+            Line::from(Span::styled(symbol, Style::default().fg(color)))
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+/// Narrowest the message column gets before other columns are hidden.
+const MIN_MESSAGE_WIDTH: u16 = 30;
+
+/// A column of the flat logs table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Timestamp,
+    Level,
+    Logger,
+    Message,
+    /// Index into the configured extra columns.
+    Custom(usize),
+    TraceId,
+    Stacktrace,
+    Score,
+}
+
+impl Column {
+    fn header(self, app: &App) -> &str {
+        match self {
+            Column::Timestamp => "Timestamp",
+            Column::Level => "Level",
+            Column::Logger => "Logger",
+            Column::Message => "Message",
+            Column::Custom(i) => &app.config.columns[i].header,
+            Column::TraceId => "Trace",
+            Column::Stacktrace => "ST",
+            Column::Score => "Score",
+        }
+    }
+
+    fn width(self, app: &App) -> Constraint {
+        match self {
+            Column::Timestamp => Constraint::Length(14),
+            Column::Level => Constraint::Length(7),
+            Column::Logger => Constraint::Length(if app.dense { 20 } else { 35 }),
+            Column::Message => Constraint::Fill(1),
+            Column::Custom(i) => Constraint::Length(app.config.columns[i].width),
+            Column::TraceId => Constraint::Length(32),
+            Column::Stacktrace => Constraint::Length(4),
+            Column::Score => Constraint::Length(8),
+        }
+    }
+
+    fn min_width(self, app: &App) -> u16 {
+        match self.width(app) {
+            Constraint::Length(width) => width,
+            _ => MIN_MESSAGE_WIDTH,
+        }
+    }
+}
+
+/// The columns that fit a table `width` wide, hiding them in
+/// `column_hide_order` as needed, and how many were hidden.
+fn visible_columns(app: &App, width: u16) -> (Vec<Column>, usize) {
+    let mut columns = vec![Column::Timestamp, Column::Level, Column::Logger, Column::Message];
+    columns.extend((0..app.config.columns.len()).map(Column::Custom));
+    if app.show_trace_id {
+        columns.push(Column::TraceId);
+    }
+    columns.push(Column::Stacktrace);
+    if app.show_score() {
+        columns.push(Column::Score);
+    }
+
+    let order = &app.config.column_hide_order;
+    let listed = |column: &Column| order.iter().any(|header| header == column.header(app));
+    let mut hide_order: Vec<Column> = columns
+        .iter()
+        .rev()
+        .filter(|column| matches!(column, Column::Custom(_) | Column::TraceId) && !listed(column))
+        .copied()
+        .collect();
+    for header in order {
+        hide_order.extend(columns.iter().copied().filter(|column| {
+            column.header(app) == header
+                && !matches!(column, Column::Timestamp | Column::Level | Column::Message)
+        }));
+    }
+
+    let available = rows_width(app, width);
+    let needed = |columns: &[Column]| columns_width(app, columns);
+    let mut hidden = 0;
+    for column in hide_order {
+        if needed(&columns) <= available {
+            break;
+        }
+        if let Some(pos) = columns.iter().position(|c| *c == column) {
+            columns.remove(pos);
+            hidden += 1;
+        }
+    }
+    (columns, hidden)
+}
+
+/// Width left for the columns of a logs table `width` wide: inside the side
+/// borders (dropped in dense mode) and the highlight symbol.
+fn rows_width(app: &App, width: u16) -> u16 {
+    width.saturating_sub(if app.dense { 2 } else { 4 })
+}
+
+/// Width `columns` take at their minimum, with a space between them.
+fn columns_width(app: &App, columns: &[Column]) -> u16 {
+    columns.iter().map(|column| column.min_width(app)).sum::<u16>()
+        + columns.len().saturating_sub(1) as u16
+}
+
+/// `text` cut into lines of at most `width` characters, keeping up to
+/// `max_lines` and marking anything left out with `…`.
+fn wrap_chars(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut lines: Vec<String> = chars.chunks(width.max(1)).map(|chunk| chunk.iter().collect()).collect();
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            last.pop();
+            last.push('…');
+        }
+    }
+    lines
+}
+
+/// The timestamp column: the entry's age if relative timestamps are on and
+/// the timestamp parses, otherwise its time of day.
+fn display_time(app: &App, log: &LogEntry) -> String {
+    if app.relative_time
+        && let Some(time) = log.time()
+    {
+        return relative_time(Utc::now().signed_duration_since(time));
+    }
+    short_time(&log.timestamp, app.config.display_timezone)
+}
+
+fn relative_time(age: TimeDelta) -> String {
+    match age.num_seconds() {
+        ..=0 => "just now".to_string(),
+        secs @ 1..60 => format!("{}s ago", secs),
+        secs @ 60..3600 => format!("{}m ago", secs / 60),
+        secs @ 3600..86400 => format!("{}h ago", secs / 3600),
+        secs => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// The time of day of a timestamp in `zone`, e.g. `12:34:56.789`, or the
+/// raw string if it does not parse.
+fn short_time(timestamp: &str, zone: DisplayTimezone) -> String {
+    match parse_timestamp(timestamp) {
+        Some(time) => zone.convert(time).format("%H:%M:%S%.3f").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// The page grouped by application: one row per application with its entry
+/// count, newest time and most severe entry, followed by its entries if expanded.
+fn render_grouped_table(f: &mut Frame, area: Rect, app: &App) {
+    let logs_focused = app.focused == Pane::Logs;
+    let header = Row::new(
+        ["Application", "Count", "Latest", "Worst", "Message"]
+            .map(|name| Cell::from(name).style(Style::default().bold())),
+    )
+    .height(1)
+    .bottom_margin(if app.dense { 0 } else { 1 });
+
+    let rows: Vec<Row> = app
+        .group_rows()
+        .into_iter()
+        .map(|row| match row {
+            GroupRow::Group(group) => {
+                let worst = &app.logs[group.worst];
+                let name = if group.application.is_empty() {
+                    Cow::Borrowed("—")
+                } else {
+                    app.config.app_display(&group.application)
+                };
+                Row::new(vec![
+                    Cell::from(sanitize(&name).into_owned()).style(Style::default().bold()),
+                    Cell::from(group.entries.len().to_string()),
+                    Cell::from(display_time(app, &app.logs[group.latest])),
+                    Cell::from(sanitize(&worst.severity).into_owned())
+                        .style(severity_style(&worst.severity)),
+                    Cell::from(sanitize(&worst.message).into_owned()),
+                ])
+            }
+            GroupRow::Entry(index) => {
+                let log = &app.logs[index];
+                Row::new(vec![
+                    Cell::from(""),
+                    Cell::from(""),
+                    Cell::from(display_time(app, log)),
+                    Cell::from(sanitize(&log.severity).into_owned())
+                        .style(severity_style(&log.severity)),
+                    Cell::from(sanitize(&log.message).into_owned()),
+                ])
+                .style(Style::default().fg(Color::Gray))
+            }
         })
         .collect();
 
     let table = Table::new(
         rows,
         [
+            Constraint::Length(35),
+            Constraint::Length(6),
             Constraint::Length(14),
             Constraint::Length(7),
-            Constraint::Length(35),
             Constraint::Fill(1),
-            Constraint::Length(4),
         ],
     )
     .header(header)
@@ -309,43 +811,204 @@ fn render_logs_table(f: &mut Frame, area: Rect, app: &App) {
         Block::default()
             .borders(Borders::ALL)
             .border_style(border_style(logs_focused))
-            .title(pane_title("Logs", 'L', logs_focused)),
+            .title(pane_title("Logs by application", 'L', logs_focused))
+            .title_top(live_tail_title(app)),
     )
     .row_highlight_style(Style::default().bg(Color::DarkGray))
     .highlight_symbol("▶ ");
 
-    let mut state = TableState::default().with_selected(Some(app.log_index));
+    let mut state = TableState::default().with_selected(Some(app.group_cursor));
     f.render_stateful_widget(table, area, &mut state);
 }
 
+/// Background for a row that just arrived, fading out over `FLASH_DURATION`.
+fn flash_style(app: &App, index: usize) -> Style {
+    let elapsed = app.flashed_at.elapsed();
+    if elapsed >= FLASH_DURATION || !app.flashed_rows.contains(&index) {
+        return Style::default();
+    }
+    let fade = 1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32();
+    let green = (30.0 + 90.0 * fade) as u8;
+    Style::default().bg(Color::Rgb(0, green, 0))
+}
+
+/// A document field as cell text; objects and arrays as compact JSON.
+fn field_text(log: &LogEntry, path: &str) -> String {
+    match log.field(path) {
+        None | Some(Value::Null) => String::new(),
+        Some(value) => opensearch::scalar_to_string(value).unwrap_or_else(|| value.to_string()),
+    }
+}
+
+/// Most stacktrace lines shown inline under a row.
+const MAX_INLINE_STACKTRACE_LINES: usize = 20;
+
+/// Indented sub-rows for a stacktrace expanded in the logs table.
+fn stacktrace_lines(stacktrace: &str) -> Vec<Line<'static>> {
+    let style = Style::default().fg(Color::Red);
+    let total = stacktrace.lines().count();
+    let mut lines: Vec<Line> = stacktrace
+        .lines()
+        .take(MAX_INLINE_STACKTRACE_LINES)
+        .map(|line| Line::styled(format!("    {}", sanitize(line)), style))
+        .collect();
+    if total > MAX_INLINE_STACKTRACE_LINES {
+        lines.push(Line::styled(
+            format!("    … {} more lines (d for details)", total - MAX_INLINE_STACKTRACE_LINES),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    lines
+}
+
+/// Right-aligned live tail indicator for the logs block.
+fn live_tail_title(app: &App) -> Line<'static> {
+    let mut spans = Vec::new();
+    if app.new_entries > 0 {
+        spans.push(Span::styled(
+            format!(" ↑ {} new entries above ", app.new_entries),
+            Style::default().fg(Color::Yellow).bold(),
+        ));
+    }
+    if app.tail_paused {
+        spans.push(Span::styled(
+            " ⏸ PAUSED — press any key to resume ",
+            Style::default().fg(Color::Black).bg(Color::Yellow).bold(),
+        ));
+    } else if app.live_tail {
+        let style = if app.page == 1 {
+            Style::default().fg(Color::Green).bold()
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(" ● LIVE ", style));
+    }
+    if app.follow {
+        spans.push(Span::styled(" ⤒ FOLLOW ", Style::default().fg(Color::Cyan).bold()));
+    }
+    Line::from(spans).right_aligned()
+}
+
 // --- Status bar ---
 
+/// Keys meaningful in the focused pane, for the status bar.
+fn key_hints(focused: Pane) -> &'static [(&'static str, &'static str)] {
+    const DROPDOWN: &[(&str, &str)] = &[
+        ("type", "filter"),
+        ("↑↓", "navigate"),
+        ("Enter", "confirm"),
+        ("Esc", "cancel"),
+        ("P/A/S/H/T/N", "switch filter"),
+        ("/", "search"),
+        ("L", "logs"),
+    ];
+    const PROMPT: &[(&str, &str)] = &[("Enter", "confirm"), ("Esc", "cancel")];
+    match focused {
+        Pane::Logs => &[
+            ("P/A/S/H/T/N", "filters"),
+            ("/", "search"),
+            ("↑↓/jk", "navigate"),
+            ("←→/hl", "page"),
+            ("g", "goto"),
+            ("f", "find"),
+            ("&", "filter page"),
+            ("R", "refresh"),
+            ("^R", "rerun"),
+            ("o", "sort"),
+            ("t", "tail"),
+            ("w", "follow newest"),
+            ("r", "rel. time"),
+            ("c/e", "dense/two-line"),
+            ("i", "trace IDs"),
+            ("0", "reset"),
+            ("W", "save default"),
+            ("Enter", "select"),
+            ("y", "copy"),
+            ("O", "export page"),
+            ("J", "copy filters"),
+            ("U", "copy Discover link"),
+            ("d", "detail"),
+            ("z/Z", "stacktrace"),
+            ("[/]", "prev/next error"),
+            ("G", "group"),
+            ("m", "mute logger"),
+            ("a", "anchor time"),
+            ("Space/b", "mark/span"),
+            ("C", "distinct"),
+            ("X", "exclude search"),
+            ("D", "show query"),
+            ("Q", "raw query"),
+            ("?", "help"),
+            ("q", "quit"),
+        ],
+        Pane::Application => &[
+            ("type", "filter"),
+            ("↑↓", "navigate"),
+            ("Space", "pick several"),
+            ("Tab", "favorite"),
+            ("Enter", "confirm"),
+            ("Esc", "cancel"),
+            ("P/A/S/H/T/N", "switch filter"),
+            ("L", "logs"),
+        ],
+        Pane::Severity => &[
+            ("type", "filter"),
+            ("↑↓", "navigate"),
+            ("Space", "pick several"),
+            ("!", "include/exclude"),
+            ("Enter", "confirm"),
+            ("Esc", "cancel"),
+            ("P/A/S/H/T/N", "switch filter"),
+            ("L", "logs"),
+        ],
+        Pane::Profile
+        | Pane::Thread
+        | Pane::TimeRange
+        | Pane::Limit
+        | Pane::SearchMode
+        | Pane::SearchFields => DROPDOWN,
+        Pane::Search => &[
+            ("type", "search"),
+            ("↑↓", "history"),
+            ("Enter", "apply"),
+            ("Esc", "back"),
+        ],
+        Pane::Find => &[("type", "find"), ("Enter", "find"), ("Esc", "cancel")],
+        Pane::LogContext => &[("↑↓/jk", "choose"), ("Enter", "select"), ("Esc", "close")],
+        Pane::QueryDebug => &[("↑↓/jk", "scroll"), ("Esc", "close")],
+        Pane::Help => &[("any key", "close")],
+        Pane::Detail => &[
+            ("↑↓/jk", "scroll"),
+            ("s", "select text"),
+            ("p", "search by field"),
+            ("v", "mode"),
+            ("x", "hex"),
+            ("w", "wrap"),
+            ("Esc", "close"),
+        ],
+        Pane::ConfirmDefaults => &[("y/Enter", "save"), ("n/Esc", "cancel")],
+        Pane::PivotField => &[("↑↓/jk", "choose"), ("Enter", "search"), ("Esc", "back")],
+        Pane::SaveEntry => &[
+            ("type", "path"),
+            ("Tab", "text/JSON/CSV"),
+            ("Enter", "save"),
+            ("Esc", "cancel"),
+        ],
+        Pane::GotoPage | Pane::CountDistinct => PROMPT,
+    }
+}
+
 fn render_status_bar(f: &mut Frame, area: Rect, app: &App) {
     let mut spans: Vec<Span> = Vec::new();
-
-    for (key, desc) in [
-        ("P", "profile"),
-        ("A", "application"),
-        ("S", "severity"),
-        ("T", "time"),
-        ("N", "limit"),
-        ("L", "logs"),
-    ] {
+    if app.config.read_only {
         spans.push(Span::styled(
-            format!(" {} ", key),
-            Style::default().fg(Color::Yellow).bold(),
+            " READ-ONLY ",
+            Style::default().fg(Color::Black).bg(Color::Red).bold(),
         ));
-        spans.push(Span::raw(format!("{}  ", desc)));
+        spans.push(Span::raw(" "));
     }
 
-    for (key, desc) in [
-        ("↑↓/jk", "navigate"),
-        ("←→/hl", "page"),
-        ("R", "refresh"),
-        ("Enter", "select"),
-        ("Esc", "back"),
-        ("q", "quit"),
-    ] {
+    for (key, desc) in key_hints(app.focused) {
         spans.push(Span::styled(
             format!(" {} ", key),
             Style::default().fg(Color::Yellow).bold(),
@@ -354,10 +1017,22 @@ fn render_status_bar(f: &mut Frame, area: Rect, app: &App) {
     }
 
     spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)));
-    spans.push(Span::raw(&app.status));
+    if app.pending {
+        spans.push(Span::styled("⟳ ", Style::default().fg(Color::Yellow)));
+    }
+    spans.push(Span::raw(sanitize(&app.status)));
 
     let position = if app.total_hits == 0 {
         " 0/0 ".to_string()
+    } else if app.total_pages() > app.max_reachable_page() {
+        format!(
+            " Page {}/{} ({}/{}), paging stops at {} ",
+            app.page,
+            app.total_pages(),
+            app.logs.len(),
+            app.total_hits,
+            app.max_reachable_page()
+        )
     } else {
         format!(
             " Page {}/{} ({}/{}) ",
@@ -368,10 +1043,25 @@ fn render_status_bar(f: &mut Frame, area: Rect, app: &App) {
         )
     };
 
+    let mut summary: Vec<Span> = Vec::new();
+    for (severity, count) in &app.severity_counts {
+        let key = severity.chars().next().map_or('?', |c| c.to_ascii_uppercase());
+        summary.push(Span::styled(format!(" {}:{}", key, count), severity_style(severity)));
+    }
+    summary.push(Span::styled(
+        format!(
+            "  {}  {} ",
+            app.config.display_timezone.label(),
+            app.sort.short_label()
+        ),
+        Style::default().fg(Color::DarkGray),
+    ));
+    summary.push(Span::raw(position));
+
     let bar = Paragraph::new(Line::from(spans)).block(
         Block::default()
             .borders(Borders::ALL)
-            .title(Line::from(position).right_aligned()),
+            .title(Line::from(summary).right_aligned()),
     );
     f.render_widget(bar, area);
 }
@@ -388,9 +1078,19 @@ fn render_log_context_menu(f: &mut Frame, logs_area: Rect, app: &App) {
     let popup = Rect::new(x, y, width, height);
     f.render_widget(Clear, popup);
 
+    // Every action copies, opens or writes something
+    let style = if app.config.read_only {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+    };
     let items: Vec<ListItem> = CONTEXT_MENU_OPTIONS
         .iter()
-        .map(|&opt| ListItem::new(opt))
+        .enumerate()
+        .map(|(i, &opt)| match app.context_option_unavailable(i) {
+            Some(_) => ListItem::new(opt).style(Style::default().fg(Color::DarkGray)),
+            None => ListItem::new(opt).style(style),
+        })
         .collect();
 
     let list = List::new(items)
@@ -413,40 +1113,783 @@ fn render_log_context_menu(f: &mut Frame, logs_area: Rect, app: &App) {
     f.render_stateful_widget(list, popup, &mut state);
 }
 
+// --- Help overlay ---
+
+/// Every key binding, grouped by where it applies.
+const HELP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Logs",
+        &[
+            ("↑↓/jk", "move"),
+            ("←→/hl", "previous/next page"),
+            ("g", "go to page"),
+            ("[/]", "previous/next error"),
+            ("Enter", "actions (or enter_action)"),
+            ("d", "detail view"),
+            ("z/Z", "stacktrace / all stacktraces"),
+            ("Space", "mark entry"),
+            ("b", "time span from marks"),
+            ("a", "anchor time on entry"),
+            ("G", "group by application"),
+        ],
+    ),
+    (
+        "Filters",
+        &[
+            ("P", "profile"),
+            ("A", "application"),
+            ("S", "severity"),
+            ("H", "thread"),
+            ("T", "time range"),
+            ("N", "page size"),
+            ("o", "sort order"),
+            ("0", "reset filters"),
+            ("W", "save as default"),
+            ("C", "count distinct values"),
+        ],
+    ),
+    (
+        "Search",
+        &[
+            ("/", "search"),
+            ("M", "search mode"),
+            ("F", "fields searched"),
+            ("X", "exclude the search"),
+            ("f", "find in page"),
+            ("n/N", "next/previous match, until Esc"),
+            ("&", "filter the page"),
+            ("Esc", "clear find / cancel fetch"),
+            ("D", "show the query sent"),
+            ("Q", "edit the raw query"),
+        ],
+    ),
+    (
+        "View",
+        &[
+            ("t", "live tail"),
+            ("w", "follow newest"),
+            ("r", "relative time"),
+            ("c", "dense rows"),
+            ("e", "two-line messages"),
+            ("i", "trace ID column"),
+            ("R", "refresh"),
+            ("Ctrl-R", "rerun and compare"),
+        ],
+    ),
+    (
+        "Export",
+        &[
+            ("y", "copy entry"),
+            ("O", "export page"),
+            ("E", "open page in $EDITOR"),
+            ("J", "copy filters as JSON"),
+            ("U", "copy Discover link"),
+            ("m", "mute logger"),
+        ],
+    ),
+    (
+        "Filter dropdowns",
+        &[
+            ("type", "narrow the list"),
+            ("↑↓", "move (wraps)"),
+            ("Home/End", "first/last"),
+            ("PgUp/PgDn", "page up/down"),
+            ("Space", "pick several"),
+            ("!", "exclude severities"),
+            ("Tab", "favorite application"),
+            ("Enter", "apply"),
+            ("Esc", "cancel"),
+        ],
+    ),
+    (
+        "Search box",
+        &[("↑↓", "history"), ("Enter", "apply"), ("Esc", "back")],
+    ),
+    (
+        "Actions menu",
+        &[("↑↓/jk", "choose"), ("Enter", "run"), ("Esc", "close")],
+    ),
+    (
+        "Detail view",
+        &[
+            ("↑↓/jk", "scroll"),
+            ("s", "select text"),
+            ("p", "search by field"),
+            ("v", "mode"),
+            ("x", "hex"),
+            ("w", "wrap"),
+            ("Esc", "close"),
+        ],
+    ),
+    ("General", &[("?", "this help"), ("q", "quit")]),
+];
+
+/// All key bindings, in as many columns as it takes to fit.
+fn render_help(f: &mut Frame, logs_area: Rect) {
+    let popup = Rect::new(
+        logs_area.x + 2.min(logs_area.width / 2),
+        logs_area.y,
+        logs_area.width.saturating_sub(4),
+        logs_area.height,
+    );
+    f.render_widget(Clear, popup);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Keys ")
+        .title_bottom(Line::from(" any key closes ").right_aligned());
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+
+    // Start a new column when a section doesn't fit below the previous one
+    let mut columns: Vec<Vec<Line>> = Vec::new();
+    let mut column: Vec<Line> = Vec::new();
+    for &(title, keys) in HELP {
+        if !column.is_empty() && column.len() + keys.len() + 2 > inner.height as usize {
+            columns.push(std::mem::take(&mut column));
+        }
+        if !column.is_empty() {
+            column.push(Line::default());
+        }
+        column.push(Line::styled(title, Style::default().fg(Color::Cyan).bold()));
+        for &(key, desc) in keys {
+            column.push(Line::from(vec![
+                Span::styled(format!(" {:<10}", key), Style::default().fg(Color::Yellow).bold()),
+                Span::raw(desc),
+            ]));
+        }
+    }
+    columns.push(column);
+
+    let areas = Layout::horizontal(vec![Constraint::Fill(1); columns.len()]).split(inner);
+    for (lines, area) in columns.into_iter().zip(areas.iter()) {
+        f.render_widget(Paragraph::new(lines), *area);
+    }
+}
+
+// --- Generated query popup ---
+
+/// The search body sent for the current page, as the user would debug it.
+fn render_query_debug(f: &mut Frame, logs_area: Rect, app: &App) {
+    let width = (logs_area.width * 4 / 5).max(40.min(logs_area.width));
+    let height = (logs_area.height * 4 / 5).max(10.min(logs_area.height));
+
+    let x = logs_area.x + (logs_area.width.saturating_sub(width)) / 2;
+    let y = logs_area.y + (logs_area.height.saturating_sub(height)) / 2;
+
+    let popup = Rect::new(x, y, width, height);
+    f.render_widget(Clear, popup);
+
+    let lines = app.query_body().map(|body| json_lines(&body)).unwrap_or_default();
+    let title = if app.raw_query.is_some() { " Query (raw) " } else { " Query " };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(title);
+    let widget = Paragraph::new(lines)
+        .block(block)
+        .scroll((app.query_scroll, 0))
+        .wrap(Wrap { trim: false });
+    f.render_widget(widget, popup);
+}
+
+fn render_pivot_fields(f: &mut Frame, logs_area: Rect, app: &App) {
+    let width = 60_u16.min(logs_area.width);
+    let height = logs_area.height.saturating_sub(4).clamp(3, 20).min(logs_area.height);
+
+    let x = logs_area.x + (logs_area.width.saturating_sub(width)) / 2;
+    let y = logs_area.y + (logs_area.height.saturating_sub(height)) / 2;
+
+    let popup = Rect::new(x, y, width, height);
+    f.render_widget(Clear, popup);
+
+    let items: Vec<ListItem> = app
+        .pivot_fields()
+        .into_iter()
+        .map(|(field, value)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(sanitize(&field).into_owned(), Style::default().fg(Color::Blue)),
+                Span::styled(" = ", Style::default().fg(Color::DarkGray)),
+                Span::raw(sanitize(&value).into_owned()),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(" Search for entries with "),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Cyan)
+                .fg(Color::Black)
+                .bold(),
+        )
+        .highlight_symbol("▶ ")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    let mut state = ListState::default().with_selected(Some(app.pivot_cursor));
+    f.render_stateful_widget(list, popup, &mut state);
+}
+
+fn render_confirm(f: &mut Frame, logs_area: Rect, question: &str) {
+    let width = (question.len() as u16 + 4).min(logs_area.width);
+    let height = 4_u16.min(logs_area.height);
+
+    let x = logs_area.x + (logs_area.width.saturating_sub(width)) / 2;
+    let y = logs_area.y + (logs_area.height.saturating_sub(height)) / 2;
+
+    let popup = Rect::new(x, y, width, height);
+    f.render_widget(Clear, popup);
+
+    let key_style = Style::default().fg(Color::Yellow).bold();
+    let lines = vec![
+        Line::from(format!(" {}", question)),
+        Line::from(vec![
+            Span::styled(" y", key_style),
+            Span::raw(" save  "),
+            Span::styled("n", key_style),
+            Span::raw(" cancel"),
+        ]),
+    ];
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Confirm ");
+    f.render_widget(Paragraph::new(lines).block(block), popup);
+}
+
+fn render_save_entry(f: &mut Frame, logs_area: Rect, app: &App) {
+    let width = 60_u16.min(logs_area.width);
+    let height = 3_u16.min(logs_area.height);
+
+    let x = logs_area.x + (logs_area.width.saturating_sub(width)) / 2;
+    let y = logs_area.y + (logs_area.height.saturating_sub(height)) / 2;
+
+    let popup = Rect::new(x, y, width, height);
+    f.render_widget(Clear, popup);
+
+    let line = Line::from(vec![
+        Span::styled(" > ", Style::default().fg(Color::Yellow)),
+        Span::raw(app.save_path.as_str()),
+        Span::styled("█", Style::default().fg(Color::Cyan)),
+    ]);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " Save {} as {} to ",
+            if app.save_page { "page" } else { "entry" },
+            app.save_format.label()
+        ));
+    f.render_widget(Paragraph::new(line).block(block), popup);
+}
+
+fn render_count_distinct(f: &mut Frame, logs_area: Rect, app: &App) {
+    let width = 40_u16.min(logs_area.width);
+    let height = 3_u16.min(logs_area.height);
+
+    let x = logs_area.x + (logs_area.width.saturating_sub(width)) / 2;
+    let y = logs_area.y + (logs_area.height.saturating_sub(height)) / 2;
+
+    let popup = Rect::new(x, y, width, height);
+    f.render_widget(Clear, popup);
+
+    let line = Line::from(vec![
+        Span::styled(" > ", Style::default().fg(Color::Yellow)),
+        Span::raw(app.distinct_field.as_str()),
+        Span::styled("█", Style::default().fg(Color::Cyan)),
+    ]);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Count distinct values of ");
+    f.render_widget(Paragraph::new(line).block(block), popup);
+}
+
+fn render_goto_page(f: &mut Frame, logs_area: Rect, app: &App) {
+    let width = 30_u16.min(logs_area.width);
+    let height = 3_u16.min(logs_area.height);
+
+    let x = logs_area.x + (logs_area.width.saturating_sub(width)) / 2;
+    let y = logs_area.y + (logs_area.height.saturating_sub(height)) / 2;
+
+    let popup = Rect::new(x, y, width, height);
+    f.render_widget(Clear, popup);
+
+    let line = Line::from(vec![
+        Span::styled(" > ", Style::default().fg(Color::Yellow)),
+        Span::raw(app.goto_input.as_str()),
+        Span::styled("█", Style::default().fg(Color::Cyan)),
+    ]);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" Go to page (1-{}) ", app.total_pages()));
+    f.render_widget(Paragraph::new(line).block(block), popup);
+}
+
+// --- Detail view ---
+
+fn render_detail(f: &mut Frame, logs_area: Rect, app: &App) {
+    f.render_widget(Clear, logs_area);
+
+    let (mode, mode_key) = if app.detail_selection.is_some() {
+        ("Select message", 's')
+    } else if app.detail_hex {
+        ("Message hex", 'x')
+    } else {
+        (app.detail_mode.label(), 'v')
+    };
+    let wrap = if app.detail_wrap { "on" } else { "off" };
+    let mode_title = Line::from(vec![
+        Span::styled(format!(" {} [", mode), Style::default().fg(Color::Cyan)),
+        Span::styled(mode_key.to_string(), Style::default().fg(Color::Yellow).bold()),
+        Span::styled(format!("]  wrap {} [", wrap), Style::default().fg(Color::Cyan)),
+        Span::styled("w", Style::default().fg(Color::Yellow).bold()),
+        Span::styled("] ", Style::default().fg(Color::Cyan)),
+    ]);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style(true))
+        .title(pane_title("Detail", 'd', true))
+        .title(mode_title.right_aligned());
+    let mut widget = Paragraph::new(detail_lines(app))
+        .block(block)
+        .scroll((app.detail_scroll, 0));
+    if app.detail_wrap {
+        widget = widget.wrap(Wrap { trim: false });
+    }
+    f.render_widget(widget, logs_area);
+}
+
+/// Lines of the selected entry in the current detail mode.
+fn detail_lines(app: &App) -> Vec<Line<'static>> {
+    let Some(log) = app.logs.get(app.log_index) else {
+        return Vec::new();
+    };
+    if let Some(selection) = app.detail_selection {
+        return selection_lines(&log.message, selection);
+    }
+    if app.detail_hex {
+        return hex_lines(log.message.as_bytes());
+    }
+    match app.detail_mode {
+        DetailMode::Text => text_lines(log, &app.config),
+        DetailMode::Json => json_lines(&log.source),
+        DetailMode::Logfmt => logfmt_lines(&log.source),
+    }
+}
+
+/// The message with the selection highlighted and the cursor marked.
+fn selection_lines(message: &str, selection: TextSelection) -> Vec<Line<'static>> {
+    let range = selection.range();
+    let style_at = |index: usize| {
+        if index == selection.cursor {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else if range.contains(&index) {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        }
+    };
+
+    let mut lines = Vec::new();
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_style = Style::default();
+    for (index, c) in message.chars().enumerate() {
+        let style = style_at(index);
+        if c == '\n' || style != run_style {
+            if !run.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut run), run_style));
+            }
+            run_style = style;
+        }
+        if c == '\n' {
+            // Keep a selected line break visible
+            if style != Style::default() {
+                spans.push(Span::styled(" ", style));
+            }
+            lines.push(Line::from(std::mem::take(&mut spans)));
+        } else {
+            run.push(c);
+        }
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, run_style));
+    }
+    lines.push(Line::from(spans));
+    lines
+}
+
+/// Number of lines the detail view currently renders in a frame `width`
+/// columns wide, used to clamp scrolling.
+pub fn detail_line_count(app: &App, width: u16) -> usize {
+    let lines = detail_lines(app);
+    if !app.detail_wrap {
+        return lines.len();
+    }
+    // Inside the borders; an estimate, as word wrapping may break lines earlier
+    let width = width.saturating_sub(2).max(1) as usize;
+    lines
+        .iter()
+        .map(|line| line.width().div_ceil(width).max(1))
+        .sum()
+}
+
+/// The entry's fields with real names, followed by any alias in parentheses.
+fn text_lines(log: &LogEntry, config: &AppConfig) -> Vec<Line<'static>> {
+    let aliases = &config.aliases;
+    let label_style = Style::default().fg(Color::Blue).bold();
+    let mut lines = Vec::new();
+
+    let zone = config.display_timezone;
+    let timestamp = match parse_timestamp(&log.timestamp) {
+        Some(time) => format!(
+            "{} ({})",
+            zone.convert(time).format("%Y-%m-%d %H:%M:%S%.3f %:z"),
+            zone.label()
+        ),
+        None => log.timestamp.clone(),
+    };
+    let trace_id = log.trace_id.clone().unwrap_or_default();
+    for (label, value) in [
+        ("Timestamp", &timestamp),
+        ("Severity", &log.severity),
+        ("Application", &log.application),
+        ("Logger", &log.logger),
+        ("Thread", &log.thread),
+        ("Profiles", &log.profiles),
+        ("Method", &log.method),
+        ("Trace ID", &trace_id),
+    ] {
+        if value.is_empty() {
+            continue;
+        }
+        let value_style = if label == "Severity" {
+            severity_style(value)
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![
+            Span::styled(format!("{:<13}", label), label_style),
+            Span::styled(sanitize(value).into_owned(), value_style),
+        ];
+        if matches!(label, "Application" | "Logger")
+            && let Some(alias) = aliases.get(value.as_str())
+        {
+            spans.push(Span::styled(
+                format!(" ({})", sanitize(alias)),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    // Everything else in the document, nested objects as dotted paths
+    let mut extra = Vec::new();
+    for (key, value) in &log.extra {
+        flatten_field(key.clone(), value, &mut extra);
+    }
+    for (key, value) in extra {
+        if key == config.severity_field {
+            continue;
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<12} ", key), label_style),
+            Span::raw(sanitize(&value.replace('\n', " ")).into_owned()),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    lines.extend(log.message.lines().map(|l| Line::from(sanitize(l).into_owned())));
+
+    if !log.stacktrace.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Stacktrace", label_style)));
+        lines.extend(
+            log.stacktrace
+                .lines()
+                .map(|l| Line::from(Span::styled(sanitize(l).into_owned(), Style::default().fg(Color::Red)))),
+        );
+    }
+
+    lines
+}
+
+/// `value` as `(path, text)` rows, descending into objects; arrays and
+/// scalars are one row each.
+fn flatten_field(path: String, value: &Value, rows: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                flatten_field(format!("{}.{}", path, key), value, rows);
+            }
+        }
+        Value::String(text) => rows.push((path, text.clone())),
+        other => rows.push((path, other.to_string())),
+    }
+}
+
+/// Classic hex dump: offset, 16 bytes in hex, then the printable ASCII.
+fn hex_lines(bytes: &[u8]) -> Vec<Line<'static>> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            Line::from(vec![
+                Span::styled(format!("{:08x}  ", row * 16), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{:<48}", hex.join(" ")), Style::default().fg(Color::Cyan)),
+                Span::raw(format!(" |{}|", ascii)),
+            ])
+        })
+        .collect()
+}
+
+/// Render a document as aligned `key=value` pairs with nested keys flattened
+/// into dotted paths.
+fn logfmt_lines(value: &Value) -> Vec<Line<'static>> {
+    let mut pairs = Vec::new();
+    flatten_json(String::new(), value, &mut pairs);
+    for (key, _) in &mut pairs {
+        *key = sanitize(key).into_owned();
+    }
+
+    let width = pairs.iter().map(|(k, _)| k.chars().count()).max().unwrap_or(0);
+    pairs
+        .into_iter()
+        .map(|(key, value)| {
+            let style = if is_standout_key(&key) {
+                Style::default().fg(Color::White).bold()
+            } else {
+                Style::default().fg(Color::Blue)
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<width$}", key, width = width), style),
+                Span::styled("=", Style::default().fg(Color::DarkGray)),
+                Span::raw(value),
+            ])
+        })
+        .collect()
+}
+
+fn flatten_json(prefix: String, value: &Value, out: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                flatten_json(key, v, out);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, v) in items.iter().enumerate() {
+                flatten_json(format!("{}[{}]", prefix, i), v, out);
+            }
+        }
+        Value::String(s) => {
+            let needs_quotes =
+                s.is_empty() || s.chars().any(|c| c.is_whitespace() || c == '"' || c == '=');
+            let text = if needs_quotes { json_string(s) } else { sanitize(s).into_owned() };
+            out.push((prefix, text));
+        }
+        _ => out.push((prefix, serde_json::to_string(value).unwrap_or_default())),
+    }
+}
+
+/// Pretty-print a JSON document as styled lines, colored by value type,
+/// using the same layout as `serde_json::to_string_pretty`.
+fn json_lines(value: &Value) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    push_json_value(&mut lines, 0, None, value, false);
+    lines
+}
+
+fn push_json_value(
+    lines: &mut Vec<Line<'static>>,
+    depth: usize,
+    key: Option<&str>,
+    value: &Value,
+    trailing_comma: bool,
+) {
+    let indent = "  ".repeat(depth);
+    let comma = if trailing_comma { "," } else { "" };
+
+    let mut spans = vec![Span::raw(indent.clone())];
+    if let Some(key) = key {
+        let key_style = if is_standout_key(key) {
+            Style::default().fg(Color::White).bold()
+        } else {
+            Style::default().fg(Color::Blue)
+        };
+        spans.push(Span::styled(json_string(key), key_style));
+        spans.push(Span::raw(": "));
+    }
+
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            spans.push(Span::raw("{"));
+            lines.push(Line::from(spans));
+            let last = map.len() - 1;
+            for (i, (k, v)) in map.iter().enumerate() {
+                push_json_value(lines, depth + 1, Some(k), v, i < last);
+            }
+            lines.push(Line::from(format!("{}}}{}", indent, comma)));
+        }
+        Value::Array(items) if !items.is_empty() => {
+            spans.push(Span::raw("["));
+            lines.push(Line::from(spans));
+            let last = items.len() - 1;
+            for (i, v) in items.iter().enumerate() {
+                push_json_value(lines, depth + 1, None, v, i < last);
+            }
+            lines.push(Line::from(format!("{}]{}", indent, comma)));
+        }
+        _ => {
+            let text = sanitize(&serde_json::to_string(value).unwrap_or_default()).into_owned();
+            spans.push(Span::styled(text, json_value_style(key, value)));
+            spans.push(Span::raw(comma));
+            lines.push(Line::from(spans));
+        }
+    }
+}
+
+fn is_standout_key(key: &str) -> bool {
+    key == "@timestamp" || key == "severity"
+}
+
+fn json_value_style(key: Option<&str>, value: &Value) -> Style {
+    match (key, value) {
+        (Some("@timestamp"), _) => Style::default().fg(Color::Magenta).bold(),
+        (Some("severity"), Value::String(s)) => severity_style(s).bold().reversed(),
+        (_, Value::String(_)) => Style::default().fg(Color::Green),
+        (_, Value::Number(_)) => Style::default().fg(Color::Cyan),
+        (_, Value::Bool(_)) => Style::default().fg(Color::Yellow),
+        (_, Value::Null) => Style::default().fg(Color::DarkGray).italic(),
+        _ => Style::default(),
+    }
+}
+
+/// Quote and escape a string as JSON. serde_json leaves DEL and C1 control
+/// characters as-is, so those are escaped on top.
+fn json_string(s: &str) -> String {
+    let quoted = serde_json::to_string(s).unwrap_or_else(|_| format!("\"{}\"", s));
+    sanitize(&quoted).into_owned()
+}
+
 // --- Text highlighting ---
 
-fn highlight_matches<'a>(text: &'a str, query: &str) -> Line<'a> {
+/// Replace control characters (C0, DEL and C1, which covers ESC and CSI)
+/// with visible escapes such as `\x1b`, so log text can't move the cursor or
+/// restyle the terminal. Tabs become spaces.
+///
+/// Everything derived from log data must pass through this before rendering.
+fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| c.is_control() && c != '\n') {
+        return Cow::Borrowed(text);
+    }
+    let mut clean = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' => clean.push(c),
+            '\t' => clean.push_str("    "),
+            c if c.is_control() => clean.push_str(&format!("\\x{:02x}", c as u32)),
+            c => clean.push(c),
+        }
+    }
+    Cow::Owned(clean)
+}
+
+fn highlight_matches(text: &str, query: &str) -> Line<'static> {
     if query.is_empty() {
-        return Line::from(text);
+        return Line::from(text.to_string());
     }
 
-    let lower_text = text.to_lowercase();
+    // Lowercasing can change a character's byte length (or turn it into
+    // several characters), so remember which original character each byte
+    // of the lowercased text came from and slice `text` by those.
+    let mut lower_text = String::with_capacity(text.len());
+    let mut origin: Vec<(usize, usize)> = Vec::with_capacity(text.len());
+    for (start, c) in text.char_indices() {
+        let before = lower_text.len();
+        lower_text.extend(c.to_lowercase());
+        origin.extend(std::iter::repeat_n((start, start + c.len_utf8()), lower_text.len() - before));
+    }
     let lower_query = query.to_lowercase();
-    let highlight = Style::default().fg(Color::Black).bg(Color::Yellow).bold();
 
-    let mut spans = Vec::new();
+    let mut ranges = Vec::new();
     let mut pos = 0;
+    let mut lower_pos = 0;
 
-    while let Some(match_start) = lower_text[pos..].find(&lower_query) {
-        let abs_start = pos + match_start;
-        let abs_end = abs_start + query.len();
+    while let Some(match_start) = lower_text[lower_pos..].find(&lower_query) {
+        let lower_start = lower_pos + match_start;
+        let lower_end = lower_start + lower_query.len();
+        let abs_start = origin[lower_start].0.max(pos);
+        let abs_end = origin[lower_end - 1].1;
+        lower_pos = lower_end;
+        if abs_end > pos {
+            ranges.push((abs_start, abs_end));
+            pos = abs_end;
+        }
+    }
 
-        if abs_start > pos {
-            spans.push(Span::raw(&text[pos..abs_start]));
+    highlight_ranges(text, &ranges)
+}
+
+/// `text` with the characters at `positions` highlighted like search matches.
+fn highlight_chars(text: &str, positions: &[usize]) -> Line<'static> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (n, (start, c)) in text.char_indices().enumerate() {
+        if !positions.contains(&n) {
+            continue;
+        }
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => ranges.push((start, end)),
         }
-        spans.push(Span::styled(&text[abs_start..abs_end], highlight));
-        pos = abs_end;
     }
+    highlight_ranges(text, &ranges)
+}
 
+/// `text` with the given ordered, non-overlapping byte ranges highlighted.
+fn highlight_ranges(text: &str, ranges: &[(usize, usize)]) -> Line<'static> {
+    let highlight = Style::default().fg(Color::Black).bg(Color::Yellow).bold();
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for &(start, end) in ranges {
+        if start > pos {
+            spans.push(Span::raw(text[pos..start].to_string()));
+        }
+        spans.push(Span::styled(text[start..end].to_string(), highlight));
+        pos = end;
+    }
     if pos < text.len() {
-        spans.push(Span::raw(&text[pos..]));
+        spans.push(Span::raw(text[pos..].to_string()));
     }
-
     Line::from(spans)
 }
 
 // --- Shared helpers ---
 
+fn severity_style(severity: &str) -> Style {
+    match severity {
+        "ERROR" => Style::default().fg(Color::Red).bold(),
+        "WARN" => Style::default().fg(Color::Yellow),
+        "INFO" => Style::default().fg(Color::Green),
+        "DEBUG" => Style::default().fg(Color::Blue),
+        _ => Style::default(),
+    }
+}
+
 fn pane_title(name: &str, hotkey: char, focused: bool) -> Line<'static> {
     let style = if focused {
         Style::default().fg(Color::Cyan).bold()
@@ -472,3 +1915,131 @@ fn border_style(focused: bool) -> Style {
         Style::default().fg(Color::DarkGray)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Auth, FieldMapping};
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    fn app_with_logs(count: usize) -> App {
+        let mut app = App::new(AppConfig::new("http://localhost:9200".to_string(), Auth::None));
+        app.config.show_histogram = false;
+        app.focused = Pane::Logs;
+        app.logs = (0..count)
+            .map(|i| {
+                let source = json!({
+                    "@timestamp": "2024-03-01T10:00:00Z",
+                    "severity": "INFO",
+                    "logger": "com.acme.Billing",
+                    "message": format!("entry {} {}", i, "x".repeat(120)),
+                });
+                LogEntry::from_source(source, &FieldMapping::default()).unwrap()
+            })
+            .collect();
+        app
+    }
+
+    fn screen(app: &App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|f| render(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn header_stays_pinned_in_every_row_layout() {
+        for (dense, two_line) in [(false, false), (true, false), (false, true), (true, true)] {
+            let mut app = app_with_logs(100);
+            app.dense = dense;
+            app.two_line = two_line;
+            app.log_index = 99;
+            let lines = screen(&app);
+            let layout = format!("dense={} two_line={}", dense, two_line);
+            assert!(
+                lines.iter().any(|line| line.contains("Timestamp") && line.contains("Message")),
+                "header missing with {}",
+                layout
+            );
+            assert!(lines.iter().any(|line| line.contains("entry 99")), "selection not shown with {}", layout);
+        }
+    }
+
+    #[test]
+    fn two_line_rows_show_more_of_the_message() {
+        let mut app = app_with_logs(1);
+        app.two_line = true;
+        let lines = screen(&app);
+        let start = lines.iter().position(|line| line.contains("entry 0")).unwrap();
+        assert!(lines[start + 1].contains("xxxx"));
+    }
+
+    #[test]
+    fn wrap_chars_marks_cut_text() {
+        assert_eq!(wrap_chars("abcdef", 3, 2), vec!["abc", "def"]);
+        assert_eq!(wrap_chars("abcdefg", 3, 2), vec!["abc", "de…"]);
+        assert_eq!(wrap_chars("ab", 3, 2), vec!["ab"]);
+    }
+
+    #[test]
+    fn detail_text_lists_unmapped_fields() {
+        let mut config = AppConfig::new("http://localhost:9200".to_string(), Auth::None);
+        config.fields.message = "msg".to_string();
+        config.severity_field = "level".to_string();
+        let source = json!({
+            "@timestamp": "2024-03-01T10:00:00Z",
+            "level": "WARN",
+            "msg": "upstream timed out",
+            "host": "web-1",
+            "http": { "status": 502, "path": "/api/orders" },
+        });
+        let log = LogEntry::from_source(source, &config.fields).unwrap();
+        let text: Vec<String> = text_lines(&log, &config)
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect();
+
+        assert!(text.contains(&"host         web-1".to_string()));
+        assert!(text.contains(&"http.path    /api/orders".to_string()));
+        assert!(text.contains(&"http.status  502".to_string()));
+        assert!(!text.iter().any(|line| line.starts_with("msg") || line.starts_with("level")));
+        assert!(text.contains(&"upstream timed out".to_string()));
+    }
+
+    /// The highlighted parts of a line, in order.
+    fn highlighted(line: &Line) -> Vec<String> {
+        line.spans
+            .iter()
+            .filter(|span| span.style.bg == Some(Color::Yellow))
+            .map(|span| span.content.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn highlight_matches_ignores_case_beyond_ascii() {
+        assert_eq!(highlighted(&highlight_matches("CAFÉ au lait", "café")), ["CAFÉ"]);
+        assert_eq!(highlighted(&highlight_matches("crème brûlée", "BRÛLÉE")), ["brûlée"]);
+        assert_eq!(highlighted(&highlight_matches("STRAßE 5", "ße")), ["ßE"]);
+    }
+
+    #[test]
+    fn highlight_matches_covers_characters_that_grow_when_lowercased() {
+        // 'İ' lowercases to 'i' plus a combining dot, two characters
+        assert_eq!(highlighted(&highlight_matches("İstanbul", "i")), ["İ"]);
+        assert_eq!(highlighted(&highlight_matches("İİ ok", "İ")), ["İ", "İ"]);
+        let line = highlight_matches("İstanbul İzmir", "izmir");
+        assert!(highlighted(&line).is_empty());
+        let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(text, "İstanbul İzmir");
+    }
+
+    #[test]
+    fn highlight_matches_does_not_overlap_repeats() {
+        assert_eq!(highlighted(&highlight_matches("aaaa", "aa")), ["aa", "aa"]);
+        assert_eq!(highlighted(&highlight_matches("aaa", "aa")), ["aa"]);
+        assert_eq!(highlighted(&highlight_matches("abab-ab", "AB")), ["ab", "ab", "ab"]);
+    }
+}